@@ -80,6 +80,41 @@ programCommand('send')
     console.log('Success');
   })
 
+programCommand('tokenize')
+  .action(async (options, cmd) => {
+    console.log("Tokenize rNFT");
+
+    await initialize();
+
+    await tokr.tokenize({
+        name: 'This is an NFT',
+        symbol: 'rNFT',
+        uri: 'https://fazymvttg4pmy7ebypj67iadpiro3z6wxxzfwmmu7modia2ttwha.arweave.net/KDOGVnM3Hsx8gcPT76ADeiLt59a98lsxlPscNANTnY4/'
+      },
+      157,
+    );
+
+    console.log('Success');
+  })
+
+programCommand('sendCapTable')
+  .argument('<csv>', 'cap table of wallet,amount rows')
+  .action(async (csv, options, cmd) => {
+    console.log("Send Fractional rNFT Shares to a Cap Table");
+
+    await initialize();
+
+    const results = await tokr.distributeCapTable(
+      new PublicKey("DoNcYcnptwJ51earju4RZg5fcrhTmAvLRSK2b8EWVHBG"), // vault address 
+      new PublicKey("FQ5iShFsGfvJXERyVJbMS9DzJ1fKk6JXnmXuJdDjqiQd"), // token mint address
+      csv,
+    );
+
+    const failed = results.filter(result => result.error);
+    console.log(`${results.length - failed.length} rows sent, ${failed.length} failed`);
+    failed.forEach(result => console.log(`Row ${result.row} ${result.wallet}: ${result.error}`));
+  })
+
 function programCommand(name: string) {
   return program
     .command(name)
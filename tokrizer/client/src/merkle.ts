@@ -0,0 +1,67 @@
+import { createHash } from 'crypto';
+import { PublicKey } from '@solana/web3.js';
+import { BN } from '@project-serum/anchor';
+
+/**
+ * Merkle tree of (holder, balance) leaves, matching `snapshot_leaf` and `verify_merkle_proof` in the program.
+ * Leaves are prefixed with 0 and nodes with 1, and each pair of nodes is hashed in sorted order.
+ */
+
+export type SnapshotEntry = {
+  holder: PublicKey;
+  balance: number;
+};
+
+function sha256(...chunks: Buffer[]): Buffer {
+  const hash = createHash('sha256');
+  chunks.forEach(chunk => hash.update(chunk));
+  return hash.digest();
+}
+
+export function snapshotLeaf(holder: PublicKey, balance: number): Buffer {
+  return sha256(Buffer.from([0]), holder.toBuffer(), new BN(balance).toArrayLike(Buffer, 'le', 8));
+}
+
+function hashPair(a: Buffer, b: Buffer): Buffer {
+  return Buffer.compare(a, b) <= 0 ? sha256(Buffer.from([1]), a, b) : sha256(Buffer.from([1]), b, a);
+}
+
+export class MerkleTree {
+  layers: Buffer[][];
+
+  constructor(leaves: Buffer[]) {
+    if (leaves.length === 0) {
+      throw new Error("Merkle tree needs at least one leaf");
+    }
+    this.layers = [leaves];
+    while (this.layers[this.layers.length - 1].length > 1) {
+      const layer = this.layers[this.layers.length - 1];
+      const next: Buffer[] = [];
+      for (let i = 0; i < layer.length; i += 2) {
+        // An odd node out is carried up to the next layer as is
+        next.push(i + 1 < layer.length ? hashPair(layer[i], layer[i + 1]) : layer[i]);
+      }
+      this.layers.push(next);
+    }
+  }
+
+  root(): Buffer {
+    return this.layers[this.layers.length - 1][0];
+  }
+
+  proof(index: number): Buffer[] {
+    const proof: Buffer[] = [];
+    for (const layer of this.layers.slice(0, -1)) {
+      const sibling = index % 2 === 0 ? index + 1 : index - 1;
+      if (sibling < layer.length) {
+        proof.push(layer[sibling]);
+      }
+      index = Math.floor(index / 2);
+    }
+    return proof;
+  }
+}
+
+export function verifyProof(proof: Buffer[], root: Buffer, leaf: Buffer): boolean {
+  return proof.reduce((node, sibling) => hashPair(node, sibling), leaf).equals(root);
+}
@@ -7,7 +7,9 @@ import {
   PublicKey,
   LAMPORTS_PER_SOL,
   SYSVAR_RENT_PUBKEY,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
   SystemProgram,
+  Ed25519Program,
   TransactionInstruction,
   Transaction,
   sendAndConfirmTransaction,
@@ -17,9 +19,11 @@ import fs from 'mz/fs';
 import path from 'path';
 import * as borsh from 'borsh';
 import { getPayer, getRpcUrl, createKeypairFromFile } from './utils';
+import { BN } from '@project-serum/anchor';
 import { programs } from '@metaplex/js';
 import { Vault, SafetyDepositBox } from '@metaplex-foundation/mpl-token-vault';
-import { TokrizeArgs, TokrizeSchema, AddTokenArgs, AddTokenSchema, VaultArgs, VaultSchema, SendFractionSchema, SendFractionArgs, FractionalizeSchema, FractionalizeArgs } from './tokrData';
+import { TokrizeArgs, TokrizeSchema, AddTokenArgs, AddTokenSchema, VaultArgs, VaultSchema, SendFractionSchema, SendFractionArgs, FractionalizeSchema, FractionalizeArgs, TokenizeSchema, TokenizeArgs, BurnSharesSchema, BurnSharesArgs, CloseVaultSchema, CloseVaultArgs, SendFractionBatchSchema, SendFractionBatchArgs, OpenOfferingSchema, OpenOfferingArgs, BuySharesSchema, BuySharesArgs, CloseOfferingSchema, CloseOfferingArgs, FinalizeOfferingSchema, FinalizeOfferingArgs, ClaimPurchaseSchema, ClaimPurchaseArgs, RefundPurchaseSchema, RefundPurchaseArgs, InitDistributionSchema, InitDistributionArgs, AmountSchema, AmountArgs, ClaimIncomeSchema, ClaimIncomeArgs, SnapshotSchema, SnapshotArgs, ClaimSnapshotSchema, ClaimSnapshotArgs, GovernanceSchema, GovernanceArgs, CombineVaultProposalSchema, CombineVaultProposalArgs, UpdatePriceProposalSchema, UpdatePriceProposalArgs, CastVoteSchema, CastVoteArgs, ProposalInstructionSchema, ProposalInstructionArgs, CreateAllowlistSchema, CreateAllowlistArgs, AllowlistEntrySchema, AllowlistEntryArgs, RemoveAllowlistEntrySchema, RemoveAllowlistEntryArgs, TransferSharesSchema, TransferSharesArgs, VestingSchedule, ClaimVestedSchema, ClaimVestedArgs, HolderRegistrySchema, HolderRegistryArgs, ComplianceProfileSchema, ComplianceProfileArgs, TransferRestriction, SetAttesterSchema, SetAttesterArgs, KycAttestationSchema, KycAttestation, DenyListInstructionSchema, DenyListInstructionArgs, ForceTransferSchema, ForceTransferArgs, ApproveClawbackSchema, ApproveClawbackArgs, RecoverySchema, RecoveryArgs, RecoveryInstructionSchema, RecoveryInstructionArgs, ConfigSchema, ConfigArgs, Role, RoleSchema, RoleArgs, WithdrawFeesSchema, WithdrawFeesArgs, PauseSchema, PauseArgs, ClosePurchaseSchema, ClosePurchaseArgs } from './tokrData';
+import { MerkleTree, SnapshotEntry, snapshotLeaf } from './merkle';
 
 const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
   "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s" // devent
@@ -217,6 +221,10 @@ async function createMintNftInstruction(args: TokrizeArgs, destination: PublicKe
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(destination), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Tokenizer), isSigner: false, isWritable: false },
+        { pubkey: await getTreasuryAddress(), isSigner: false, isWritable: true },
       ],
       programId,
       data: data
@@ -227,7 +235,7 @@ async function createMintNftInstruction(args: TokrizeArgs, destination: PublicKe
 /*************************************************
  *  Tokr Instructions - Create Vault
  *************************************************/
-export async function createVault(): Promise<void> {
+export async function createVault(allowFurtherShareCreation = false, fractionDecimals = 0, complianceProfile?: PublicKey): Promise<void> {
 
   let vaultSeed = (Math.random() + 1).toString(36).substring(2) + (Math.random() + 1).toString(36).substring(2);
 
@@ -235,7 +243,12 @@ export async function createVault(): Promise<void> {
 
   const data = Buffer.from(borsh.serialize(
     VaultSchema,
-    new VaultArgs({ vault_bump: vaultBump, vault_seed: vaultSeed })
+    new VaultArgs({
+      vault_bump: vaultBump,
+      vault_seed: vaultSeed,
+      allow_further_share_creation: allowFurtherShareCreation,
+      fraction_decimals: fractionDecimals,
+    })
   ));
 
   const vaultMintAuthority = await Vault.getPDA(vaultKey);
@@ -273,6 +286,10 @@ export async function createVault(): Promise<void> {
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
         { pubkey: NATIVE_MINT, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Tokenizer), isSigner: false, isWritable: false },
+        { pubkey: await getTreasuryAddress(), isSigner: false, isWritable: true },
+        ...await getVaultComplianceKeys(vaultKey, complianceProfile),
       ],
       programId,
       data: data
@@ -305,6 +322,7 @@ export async function addTokenToVault(vaultAddress: PublicKey, mintAddress: Publ
   const tokenAta = await getTokenWallet(payer.publicKey, mintAddress); // todo replace with treasury
   // const tokenStore = Keypair.generate() // todo use PDA
   const tokenStoreKey = (await PublicKey.findProgramAddress([Buffer.from("store"), vaultAddress.toBuffer(), mintAddress.toBuffer()], programId))[0]
+  const portfolioKey = await getPortfolio(vaultAddress);
 
   console.log("tokenAta: ", tokenAta.toBase58());
   console.log("vault: ", vaultAddress.toBase58());
@@ -312,6 +330,7 @@ export async function addTokenToVault(vaultAddress: PublicKey, mintAddress: Publ
   console.log("safetyDepositBox: ", safetyDepositBox.toBase58());
   console.log("transferAuthority: ", transferAuthorityKey.toBase58());
   console.log("tokenStoreKey: ", tokenStoreKey.toBase58());
+  console.log("portfolio: ", portfolioKey.toBase58());
 
   const data = Buffer.from(borsh.serialize(
     AddTokenSchema,
@@ -335,6 +354,9 @@ export async function addTokenToVault(vaultAddress: PublicKey, mintAddress: Publ
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: portfolioKey, isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
       ],
       programId,
       data: data
@@ -385,6 +407,9 @@ export async function fractionalize(
         { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: true },
         { pubkey: TOKEN_VAULT_PROGRAM_ID, isSigner: false, isWritable: false },
         { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getPortfolio(vaultAddress), isSigner: false, isWritable: false },
       ],
       programId,
       data: data
@@ -404,7 +429,7 @@ export async function fractionalize(
 /*************************************************
  *  Tokr Instructions - Send Share
  *************************************************/
-export async function sendShare(vaultAddress: PublicKey, destination: PublicKey, mintAddress: PublicKey, amount: number) {
+export async function sendShare(vaultAddress: PublicKey, destination: PublicKey, mintAddress: PublicKey, amount: number, vesting?: VestingSchedule, attestation?: TransactionInstruction) {
 
   console.log("Sending fraction of NFT");
   console.log("Destination: ", destination.toBase58());
@@ -421,11 +446,15 @@ export async function sendShare(vaultAddress: PublicKey, destination: PublicKey,
 
   const data = Buffer.from(borsh.serialize(
     SendFractionSchema,
-    new SendFractionArgs({ number_of_shares: amount })
+    new SendFractionArgs({ number_of_shares: amount, vesting })
   ));
 
   const transferAuthorityKey = (await PublicKey.findProgramAddress([Buffer.from("vault"), TOKEN_VAULT_PROGRAM_ID.toBuffer(), vaultAddress.toBuffer()], TOKEN_VAULT_PROGRAM_ID))[0]
 
+  const { allowlist, entry } = await getAllowlistAccounts(vaultAddress, destination);
+  const { vesting: vestingKey, vestingShares } = await getVestingAccounts(vaultAddress, destination);
+  const { vaultCompliance, complianceProfile } = await getComplianceAccounts(vaultAddress);
+
   const instruction = new TransactionInstruction(
     {
       keys: [
@@ -443,6 +472,155 @@ export async function sendShare(vaultAddress: PublicKey, destination: PublicKey,
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: entry, isSigner: false, isWritable: false },
+        { pubkey: vestingKey, isSigner: false, isWritable: true },
+        { pubkey: vestingShares, isSigner: false, isWritable: true },
+        { pubkey: await getHolderRegistry(fractionMint), isSigner: false, isWritable: true },
+        { pubkey: vaultCompliance, isSigner: false, isWritable: false },
+        { pubkey: complianceProfile, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(destination), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(...(attestation ? [attestation] : []), instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Tokenize
+ *************************************************/
+export async function tokenize(
+  args: { name: string, symbol: string, uri: string, allowFurtherShareCreation?: boolean, fractionDecimals?: number, complianceProfile?: PublicKey },
+  shareCount: number,
+): Promise<void> {
+  let mintSeed = (Math.random() + 1).toString(36).substring(2) + (Math.random() + 1).toString(36).substring(2) + (Math.random() + 1).toString(36).substring(2);
+  const [mintKey, mintBump] = (await PublicKey.findProgramAddress([Buffer.from(mintSeed), payer.publicKey.toBuffer(), payer.publicKey.toBuffer()], programId));
+
+  let vaultSeed = (Math.random() + 1).toString(36).substring(2) + (Math.random() + 1).toString(36).substring(2);
+  const [vaultKey, vaultBump] = (await PublicKey.findProgramAddress([payer.publicKey.toBuffer(), TOKEN_VAULT_PROGRAM_ID.toBuffer(), Buffer.from(vaultSeed)], programId));
+
+  const data = Buffer.from(borsh.serialize(
+    TokenizeSchema,
+    new TokenizeArgs({
+      name: args.name,
+      symbol: args.symbol,
+      uri: args.uri,
+      mint_bump: mintBump,
+      mint_seed: mintSeed,
+      vault_bump: vaultBump,
+      vault_seed: vaultSeed,
+      allow_further_share_creation: args.allowFurtherShareCreation,
+      fraction_decimals: args.fractionDecimals,
+      number_of_shares: shareCount,
+    })
+  ));
+
+  const metadataAccount = (await PublicKey.findProgramAddress([Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mintKey.toBuffer()], TOKEN_METADATA_PROGRAM_ID))[0];
+  const tokenAta = await getTokenWallet(payer.publicKey, mintKey);
+
+  const vaultMintAuthority = await Vault.getPDA(vaultKey);
+  const externalPricingAccountKey = (await PublicKey.findProgramAddress([Buffer.from("external"), vaultKey.toBuffer(), payer.publicKey.toBuffer()], programId))[0]
+  const fractionMintkey = (await PublicKey.findProgramAddress([Buffer.from("fraction"), vaultKey.toBuffer(), payer.publicKey.toBuffer()], programId))[0]
+  const redeemTreasuryKey = (await PublicKey.findProgramAddress([vaultMintAuthority.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), NATIVE_MINT.toBuffer()], ASSOCIATED_TOKEN_PROGRAM_ID))[0]
+  const fractionTreasuryKey = (await PublicKey.findProgramAddress([vaultMintAuthority.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), fractionMintkey.toBuffer()], ASSOCIATED_TOKEN_PROGRAM_ID))[0]
+
+  const transferAuthorityKey = (await PublicKey.findProgramAddress([Buffer.from("transfer"), vaultKey.toBuffer(), mintKey.toBuffer()], programId))[0]
+  const tokenStoreKey = (await PublicKey.findProgramAddress([Buffer.from("store"), vaultKey.toBuffer(), mintKey.toBuffer()], programId))[0]
+  const safetyDepositBox = await SafetyDepositBox.getPDA(vaultKey, mintKey);
+
+  console.log("Mint: ", mintKey.toBase58());
+  console.log("vaultKey:", vaultKey.toBase58());
+  console.log("fractionMintkey:", fractionMintkey.toBase58());
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: mintKey, isSigner: false, isWritable: true },
+        { pubkey: metadataAccount, isSigner: false, isWritable: true },
+        { pubkey: tokenAta, isSigner: false, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: vaultKey, isSigner: false, isWritable: true },
+        { pubkey: vaultMintAuthority, isSigner: false, isWritable: true },
+        { pubkey: externalPricingAccountKey, isSigner: false, isWritable: true },
+        { pubkey: fractionMintkey, isSigner: false, isWritable: true },
+        { pubkey: redeemTreasuryKey, isSigner: false, isWritable: true },
+        { pubkey: fractionTreasuryKey, isSigner: false, isWritable: true },
+        { pubkey: transferAuthorityKey, isSigner: false, isWritable: true },
+        { pubkey: tokenStoreKey, isSigner: false, isWritable: true },
+        { pubkey: safetyDepositBox, isSigner: false, isWritable: true },
+        { pubkey: await getPortfolio(vaultKey), isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultKey), isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_VAULT_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: NATIVE_MINT, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(payer.publicKey), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Tokenizer), isSigner: false, isWritable: false },
+        { pubkey: await getTreasuryAddress(), isSigner: false, isWritable: true },
+        ...await getVaultComplianceKeys(vaultKey, args.complianceProfile),
+      ],
+      programId,
+      data: data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Transaction id:", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Burn Treasury Shares
+ *************************************************/
+export async function burnTreasuryShares(vaultAddress: PublicKey, amount: number) {
+
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const vaultMintAuthority = await Vault.getPDA(vaultAddress);
+  const burnAccountKey = (await PublicKey.findProgramAddress([Buffer.from("burn"), vaultAddress.toBuffer()], programId))[0]
+
+  const data = Buffer.from(borsh.serialize(
+    BurnSharesSchema,
+    new BurnSharesArgs({ number_of_shares: amount })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: true },
+        { pubkey: vaultMintAuthority, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: true },
+        { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: true },
+        { pubkey: burnAccountKey, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_VAULT_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
       ],
       programId,
       data
@@ -458,15 +636,2344 @@ export async function sendShare(vaultAddress: PublicKey, destination: PublicKey,
   console.log("Tx: ", tx);
 }
 
+/*************************************************
+ *  Tokr Instructions - Close Vault
+ *************************************************/
+export async function closeVault(vaultAddress: PublicKey, rentReceiver: PublicKey) {
+
+  const burnAccountKey = (await PublicKey.findProgramAddress([Buffer.from("burn"), vaultAddress.toBuffer()], programId))[0]
 
-export const getTokenWallet = async function (
-  wallet: PublicKey,
-  mint: PublicKey,
+  const data = Buffer.from(borsh.serialize(
+    CloseVaultSchema,
+    new CloseVaultArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: rentReceiver, isSigner: false, isWritable: true },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: await getPortfolio(vaultAddress), isSigner: false, isWritable: true },
+        { pubkey: burnAccountKey, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Send Share Batch
+ *************************************************/
+
+/**
+ * Solana's maximum serialized transaction size
+ */
+const MAX_TRANSACTION_SIZE = 1232;
+
+/**
+ * Creating an ATA is the expensive part of a send, this keeps a batch inside the default compute budget
+ */
+const MAX_BATCH_RECIPIENTS = 8;
+
+export type CapTableRow = {
+  row: number;
+  destination: PublicKey;
+  amount: BN;
+};
+
+export type CapTableResult = {
+  row: number;
+  wallet: string;
+  amount: string;
+  tx?: string;
+  error?: string;
+};
+
+const U64_MAX = new BN('18446744073709551615');
+
+function capTableResult(row: CapTableRow, outcome: { tx?: string, error?: string }): CapTableResult {
+  return { row: row.row, wallet: row.destination.toBase58(), amount: row.amount.toString(), ...outcome };
+}
+
+/**
+ * Parse a cap table CSV of `wallet,amount` lines, an optional header line is skipped.
+ * Rows without a valid wallet or a whole number of shares from 1 to u64 max are returned as invalid instead.
+ */
+export async function readCapTable(csvPath: string): Promise<{ rows: CapTableRow[], invalid: CapTableResult[] }> {
+  const lines = (await fs.readFile(csvPath, 'utf8')).split(/\r?\n/);
+  const rows: CapTableRow[] = [];
+  const invalid: CapTableResult[] = [];
+  lines.forEach((line, index) => {
+    if (!line.trim()) {
+      return;
+    }
+    const fields = line.split(',').map(field => field.trim());
+    const [wallet = '', amount = ''] = fields;
+    if (index === 0 && !/\d/.test(amount)) {
+      return;
+    }
+
+    const fail = (error: string) => invalid.push({ row: index + 1, wallet, amount, error });
+    if (fields.length !== 2) {
+      fail(`Expected wallet,amount but found ${fields.length} fields`);
+      return;
+    }
+    let destination: PublicKey;
+    try {
+      destination = new PublicKey(wallet);
+    } catch {
+      fail(`Invalid wallet address ${wallet}`);
+      return;
+    }
+    if (!/^\d+$/.test(amount) || new BN(amount).isZero() || new BN(amount).gt(U64_MAX)) {
+      fail(`Invalid share amount ${amount}`);
+      return;
+    }
+    rows.push({ row: index + 1, destination, amount: new BN(amount) });
+  });
+  return { rows, invalid };
+}
+
+export async function createSendShareBatchInstruction(vaultAddress: PublicKey, mintAddress: PublicKey, rows: CapTableRow[]) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const fractionMint = new PublicKey(vault.data.fractionMint);
+
+  const data = Buffer.from(borsh.serialize(
+    SendFractionBatchSchema,
+    new SendFractionBatchArgs({ amounts: rows.map(row => row.amount) })
+  ));
+
+  const transferAuthorityKey = (await PublicKey.findProgramAddress([Buffer.from("vault"), TOKEN_VAULT_PROGRAM_ID.toBuffer(), vaultAddress.toBuffer()], TOKEN_VAULT_PROGRAM_ID))[0]
+  const { vaultCompliance, complianceProfile } = await getComplianceAccounts(vaultAddress);
+
+  const keys = [
+    { pubkey: mintAddress, isSigner: false, isWritable: true },
+    { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+    { pubkey: transferAuthorityKey, isSigner: false, isWritable: true },
+    { pubkey: vaultAddress, isSigner: false, isWritable: true },
+    { pubkey: new PublicKey(vault.data.authority), isSigner: false, isWritable: true },
+    { pubkey: fractionMint, isSigner: false, isWritable: true },
+    { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: true },
+    { pubkey: TOKEN_VAULT_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: await getAllowlistAddress(vaultAddress), isSigner: false, isWritable: false },
+    { pubkey: await getHolderRegistry(fractionMint), isSigner: false, isWritable: true },
+    { pubkey: vaultCompliance, isSigner: false, isWritable: false },
+    { pubkey: complianceProfile, isSigner: false, isWritable: false },
+    { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+    { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+  ];
+  for (const row of rows) {
+    keys.push({ pubkey: row.destination, isSigner: false, isWritable: true });
+    keys.push({ pubkey: await getTokenWallet(row.destination, fractionMint), isSigner: false, isWritable: true });
+    keys.push({ pubkey: (await getAllowlistAccounts(vaultAddress, row.destination)).entry, isSigner: false, isWritable: false });
+    keys.push({ pubkey: await getDeniedWalletAddress(row.destination), isSigner: false, isWritable: false });
+  }
+
+  return new TransactionInstruction({ keys, programId, data });
+}
+
+/**
+ * Split the cap table into as few SendShareBatch transactions as fit the size and compute limits.
+ * A row too large to send even on its own is returned as unsendable.
+ */
+export async function packCapTable(vaultAddress: PublicKey, mintAddress: PublicKey, rows: CapTableRow[]): Promise<{ batches: CapTableRow[][], unsendable: CapTableResult[] }> {
+  const { blockhash } = await connection.getRecentBlockhash();
+  const fits = async (batch: CapTableRow[]) => {
+    const tx = new Transaction().add(await createSendShareBatchInstruction(vaultAddress, mintAddress, batch));
+    tx.feePayer = payer.publicKey;
+    tx.recentBlockhash = blockhash;
+    return tx.serialize({ requireAllSignatures: false, verifySignatures: false }).length <= MAX_TRANSACTION_SIZE;
+  };
+
+  const batches: CapTableRow[][] = [];
+  const unsendable: CapTableResult[] = [];
+  let batch: CapTableRow[] = [];
+  for (const row of rows) {
+    const candidate = [...batch, row];
+    if (candidate.length <= MAX_BATCH_RECIPIENTS && await fits(candidate)) {
+      batch = candidate;
+      continue;
+    }
+    if (batch.length > 0) {
+      batches.push(batch);
+    }
+    if (await fits([row])) {
+      batch = [row];
+    } else {
+      unsendable.push(capTableResult(row, { error: 'Row does not fit in a transaction on its own' }));
+      batch = [];
+    }
+  }
+  if (batch.length > 0) {
+    batches.push(batch);
+  }
+  return { batches, unsendable };
+}
+
+/**
+ * Distribute shares to every row of a cap table CSV, reporting which rows succeeded
+ */
+export async function distributeCapTable(vaultAddress: PublicKey, mintAddress: PublicKey, csvPath: string): Promise<CapTableResult[]> {
+  const { rows, invalid } = await readCapTable(csvPath);
+  const { batches, unsendable } = await packCapTable(vaultAddress, mintAddress, rows);
+  console.log(`Sending ${rows.length - unsendable.length} cap table rows in ${batches.length} transactions`);
+
+  const results: CapTableResult[] = [...invalid, ...unsendable];
+  for (const batch of batches) {
+    try {
+      const tx = await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(await createSendShareBatchInstruction(vaultAddress, mintAddress, batch)),
+        [payer],
+      );
+      console.log("Tx: ", tx);
+      batch.forEach(row => results.push(capTableResult(row, { tx })));
+    } catch (err) {
+      const error = (err as Error).message;
+      console.log(`Rows ${batch.map(row => row.row).join(', ')} failed:`, error);
+      batch.forEach(row => results.push(capTableResult(row, { error })));
+    }
+  }
+  return results.sort((a, b) => a.row - b.row);
+}
+
+/*************************************************
+ *  Tokr Instructions - Offering
+ *************************************************/
+export async function getOfferingAccounts(vaultAddress: PublicKey) {
+  const offering = (await PublicKey.findProgramAddress([Buffer.from("offering"), vaultAddress.toBuffer()], programId))[0];
+  const offeringShares = (await PublicKey.findProgramAddress([Buffer.from("offering_shares"), offering.toBuffer()], programId))[0];
+  const proceeds = (await PublicKey.findProgramAddress([Buffer.from("proceeds"), offering.toBuffer()], programId))[0];
+  return { offering, offeringShares, proceeds };
+}
+
+/**
+ * Counts the vault's offerings, so purchase caps start again in each new one
+ */
+export async function getOfferingNonce(vaultAddress: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("offering_nonce"), vaultAddress.toBuffer()], programId))[0];
+}
+
+export async function openOffering(
+  vaultAddress: PublicKey,
+  paymentMint: PublicKey,
+  args: { price_per_share: number, number_of_shares: number, start_ts: number, end_ts: number, max_shares_per_wallet: number, min_shares?: number },
 ) {
-  return (
-    await PublicKey.findProgramAddress(
-      [wallet.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), mint.toBuffer()],
-      ASSOCIATED_TOKEN_PROGRAM_ID,
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const vaultMintAuthority = await Vault.getPDA(vaultAddress);
+  const { offering, offeringShares, proceeds } = await getOfferingAccounts(vaultAddress);
+  console.log("Offering: ", offering.toBase58());
+
+  const data = Buffer.from(borsh.serialize(
+    OpenOfferingSchema,
+    new OpenOfferingArgs(args)
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: true },
+        { pubkey: vaultMintAuthority, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: true },
+        { pubkey: offering, isSigner: false, isWritable: true },
+        { pubkey: offeringShares, isSigner: false, isWritable: true },
+        { pubkey: paymentMint, isSigner: false, isWritable: false },
+        { pubkey: proceeds, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_VAULT_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getOfferingNonce(vaultAddress), isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function buyShares(vaultAddress: PublicKey, paymentMint: PublicKey, amount: number, attestation?: TransactionInstruction) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const fractionMint = new PublicKey(vault.data.fractionMint);
+  const { offering, offeringShares, proceeds } = await getOfferingAccounts(vaultAddress);
+  const purchase = (await PublicKey.findProgramAddress([Buffer.from("purchase"), offering.toBuffer(), payer.publicKey.toBuffer()], programId))[0];
+  const { allowlist, entry } = await getAllowlistAccounts(vaultAddress, payer.publicKey);
+  const { vaultCompliance, complianceProfile } = await getComplianceAccounts(vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    BuySharesSchema,
+    new BuySharesArgs({ number_of_shares: amount })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, paymentMint), isSigner: false, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, fractionMint), isSigner: false, isWritable: true },
+        { pubkey: offering, isSigner: false, isWritable: true },
+        { pubkey: purchase, isSigner: false, isWritable: true },
+        { pubkey: offeringShares, isSigner: false, isWritable: true },
+        { pubkey: proceeds, isSigner: false, isWritable: true },
+        { pubkey: fractionMint, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: entry, isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(fractionMint), isSigner: false, isWritable: true },
+        { pubkey: vaultCompliance, isSigner: false, isWritable: false },
+        { pubkey: complianceProfile, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(payer.publicKey), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(...(attestation ? [attestation] : []), instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function closeOffering(vaultAddress: PublicKey, paymentMint: PublicKey) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const { offering, offeringShares, proceeds } = await getOfferingAccounts(vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    CloseOfferingSchema,
+    new CloseOfferingArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: payer.publicKey, isSigner: false, isWritable: true },
+        { pubkey: offering, isSigner: false, isWritable: true },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: offeringShares, isSigner: false, isWritable: true },
+        { pubkey: proceeds, isSigner: false, isWritable: true },
+        { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, paymentMint), isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getFeeAccount(paymentMint), isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function finalizeOffering(vaultAddress: PublicKey, paymentMint: PublicKey, vaultAuthority: PublicKey) {
+  const { offering, proceeds } = await getOfferingAccounts(vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    FinalizeOfferingSchema,
+    new FinalizeOfferingArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: offering, isSigner: false, isWritable: true },
+        { pubkey: proceeds, isSigner: false, isWritable: true },
+        { pubkey: await getTokenWallet(vaultAuthority, paymentMint), isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getFeeAccount(paymentMint), isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function claimPurchase(vaultAddress: PublicKey, buyer: PublicKey, attestation?: TransactionInstruction) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const fractionMint = new PublicKey(vault.data.fractionMint);
+  const { offering, offeringShares } = await getOfferingAccounts(vaultAddress);
+  const purchase = (await PublicKey.findProgramAddress([Buffer.from("purchase"), offering.toBuffer(), buyer.toBuffer()], programId))[0];
+  const { allowlist, entry } = await getAllowlistAccounts(vaultAddress, buyer);
+  const { vaultCompliance, complianceProfile } = await getComplianceAccounts(vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    ClaimPurchaseSchema,
+    new ClaimPurchaseArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: buyer, isSigner: false, isWritable: true },
+        { pubkey: await getTokenWallet(buyer, fractionMint), isSigner: false, isWritable: true },
+        { pubkey: offering, isSigner: false, isWritable: true },
+        { pubkey: purchase, isSigner: false, isWritable: true },
+        { pubkey: offeringShares, isSigner: false, isWritable: true },
+        { pubkey: fractionMint, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: entry, isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(fractionMint), isSigner: false, isWritable: true },
+        { pubkey: vaultCompliance, isSigner: false, isWritable: false },
+        { pubkey: complianceProfile, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(buyer), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(...(attestation ? [attestation] : []), instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Refund a buyer's escrowed purchase. After a successful offering only a buyer on the deny list can be refunded,
+ * and the payer, as vault authority, pays them back from its own account since the proceeds were already paid out.
+ */
+export async function refundPurchase(vaultAddress: PublicKey, paymentMint: PublicKey, buyer: PublicKey, fromVaultAuthority = false) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const { offering, offeringShares, proceeds } = await getOfferingAccounts(vaultAddress);
+  const purchase = (await PublicKey.findProgramAddress([Buffer.from("purchase"), offering.toBuffer(), buyer.toBuffer()], programId))[0];
+
+  const data = Buffer.from(borsh.serialize(
+    RefundPurchaseSchema,
+    new RefundPurchaseArgs()
+  ));
+
+  const keys = [
+    { pubkey: buyer, isSigner: false, isWritable: true },
+    { pubkey: await getTokenWallet(buyer, paymentMint), isSigner: false, isWritable: true },
+    { pubkey: offering, isSigner: false, isWritable: true },
+    { pubkey: vaultAddress, isSigner: false, isWritable: false },
+    { pubkey: purchase, isSigner: false, isWritable: true },
+    { pubkey: offeringShares, isSigner: false, isWritable: true },
+    { pubkey: fromVaultAuthority ? await getTokenWallet(payer.publicKey, paymentMint) : proceeds, isSigner: false, isWritable: true },
+    { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: await getDeniedWalletAddress(buyer), isSigner: false, isWritable: false },
+    { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+    { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+  ];
+  if (fromVaultAuthority) {
+    keys.push({ pubkey: payer.publicKey, isSigner: true, isWritable: false });
+  }
+
+  const instruction = new TransactionInstruction(
+    {
+      keys,
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Close a buyer's purchase record once the offering it was made in is over, returning its rent to the buyer
+ */
+export async function closePurchase(vaultAddress: PublicKey, buyer: PublicKey) {
+  const { offering } = await getOfferingAccounts(vaultAddress);
+  const purchase = (await PublicKey.findProgramAddress([Buffer.from("purchase"), offering.toBuffer(), buyer.toBuffer()], programId))[0];
+
+  const data = Buffer.from(borsh.serialize(
+    ClosePurchaseSchema,
+    new ClosePurchaseArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: buyer, isSigner: false, isWritable: true },
+        { pubkey: purchase, isSigner: false, isWritable: true },
+        { pubkey: offering, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Rental Income Distribution
+ *************************************************/
+export async function getDistributionAccounts(vaultAddress: PublicKey) {
+  const distribution = (await PublicKey.findProgramAddress([Buffer.from("distribution"), vaultAddress.toBuffer()], programId))[0];
+  const income = (await PublicKey.findProgramAddress([Buffer.from("income"), distribution.toBuffer()], programId))[0];
+  const staked = (await PublicKey.findProgramAddress([Buffer.from("staked"), distribution.toBuffer()], programId))[0];
+  return { distribution, income, staked };
+}
+
+export async function initDistribution(vaultAddress: PublicKey, manager: PublicKey, paymentMint: PublicKey) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const { distribution, income, staked } = await getDistributionAccounts(vaultAddress);
+  console.log("Distribution: ", distribution.toBase58());
+
+  const data = Buffer.from(borsh.serialize(
+    InitDistributionSchema,
+    new InitDistributionArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: manager, isSigner: false, isWritable: false },
+        { pubkey: distribution, isSigner: false, isWritable: true },
+        { pubkey: income, isSigner: false, isWritable: true },
+        { pubkey: staked, isSigner: false, isWritable: true },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: paymentMint, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(manager, Role.PropertyManager), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function depositIncome(vaultAddress: PublicKey, paymentMint: PublicKey, amount: number) {
+  const { distribution, income } = await getDistributionAccounts(vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    AmountSchema,
+    new AmountArgs({ instruction: 16, amount })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: await getTokenWallet(payer.publicKey, paymentMint), isSigner: false, isWritable: true },
+        { pubkey: distribution, isSigner: false, isWritable: true },
+        { pubkey: income, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.PropertyManager), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+async function updateStake(vaultAddress: PublicKey, paymentMint: PublicKey, data: Buffer) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const { distribution, income, staked } = await getDistributionAccounts(vaultAddress);
+  const stake = (await PublicKey.findProgramAddress([Buffer.from("stake"), distribution.toBuffer(), payer.publicKey.toBuffer()], programId))[0];
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, paymentMint), isSigner: false, isWritable: true },
+        { pubkey: distribution, isSigner: false, isWritable: true },
+        { pubkey: stake, isSigner: false, isWritable: true },
+        { pubkey: staked, isSigner: false, isWritable: true },
+        { pubkey: income, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(payer.publicKey), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function stakeShares(vaultAddress: PublicKey, paymentMint: PublicKey, amount: number) {
+  await updateStake(vaultAddress, paymentMint, Buffer.from(borsh.serialize(AmountSchema, new AmountArgs({ instruction: 17, amount }))));
+}
+
+export async function unstakeShares(vaultAddress: PublicKey, paymentMint: PublicKey, amount: number) {
+  await updateStake(vaultAddress, paymentMint, Buffer.from(borsh.serialize(AmountSchema, new AmountArgs({ instruction: 18, amount }))));
+}
+
+export async function claimIncome(vaultAddress: PublicKey, paymentMint: PublicKey) {
+  await updateStake(vaultAddress, paymentMint, Buffer.from(borsh.serialize(ClaimIncomeSchema, new ClaimIncomeArgs())));
+}
+
+/*************************************************
+ *  Tokr Instructions - Holder Snapshots
+ *************************************************/
+export type HolderSnapshot = {
+  slot: number;
+  totalShares: number;
+  entries: SnapshotEntry[];
+  tree: MerkleTree;
+};
+
+/**
+ * Read every holder of a vault's shares from chain and build the snapshot Merkle tree.
+ * Balances are summed per owner, and shares still in the fraction treasury are left out.
+ */
+export async function buildSnapshot(vaultAddress: PublicKey): Promise<HolderSnapshot> {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const vaultMintAuthority = await Vault.getPDA(vaultAddress);
+
+  const slot = await connection.getSlot();
+  const tokenAccounts = await connection.getProgramAccounts(TOKEN_PROGRAM_ID, {
+    filters: [
+      { dataSize: 165 },
+      { memcmp: { offset: 0, bytes: vault.data.fractionMint } },
+    ],
+  });
+
+  const balances = new Map<string, number>();
+  for (const { account } of tokenAccounts) {
+    const owner = new PublicKey(account.data.slice(32, 64));
+    const balance = new BN(account.data.slice(64, 72), 'le').toNumber();
+    if (balance > 0 && !owner.equals(vaultMintAuthority)) {
+      balances.set(owner.toBase58(), (balances.get(owner.toBase58()) ?? 0) + balance);
+    }
+  }
+
+  const entries = [...balances.entries()]
+    .sort(([a], [b]) => a.localeCompare(b))
+    .map(([holder, balance]) => ({ holder: new PublicKey(holder), balance }));
+  const tree = new MerkleTree(entries.map(entry => snapshotLeaf(entry.holder, entry.balance)));
+  const totalShares = entries.reduce((total, entry) => total + entry.balance, 0);
+
+  return { slot, totalShares, entries, tree };
+}
+
+export async function getSnapshotAddress(vaultAddress: PublicKey, id: number) {
+  return (await PublicKey.findProgramAddress([Buffer.from("snapshot"), vaultAddress.toBuffer(), new BN(id).toArrayLike(Buffer, 'le', 8)], programId))[0];
+}
+
+export async function createSnapshot(vaultAddress: PublicKey, id: number): Promise<HolderSnapshot> {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const holderSnapshot = await buildSnapshot(vaultAddress);
+  const snapshot = await getSnapshotAddress(vaultAddress, id);
+  console.log(`Snapshot ${snapshot.toBase58()} of ${holderSnapshot.entries.length} holders at slot ${holderSnapshot.slot}`);
+
+  const data = Buffer.from(borsh.serialize(
+    SnapshotSchema,
+    new SnapshotArgs({
+      id,
+      slot: holderSnapshot.slot,
+      merkle_root: holderSnapshot.tree.root(),
+      total_shares: holderSnapshot.totalShares,
+    })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: snapshot, isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+  return holderSnapshot;
+}
+
+export async function fundSnapshot(vaultAddress: PublicKey, id: number, paymentMint: PublicKey, amount: number) {
+  const snapshot = await getSnapshotAddress(vaultAddress, id);
+  const payout = (await PublicKey.findProgramAddress([Buffer.from("snapshot_payout"), snapshot.toBuffer()], programId))[0];
+
+  const data = Buffer.from(borsh.serialize(
+    AmountSchema,
+    new AmountArgs({ instruction: 21, amount })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, paymentMint), isSigner: false, isWritable: true },
+        { pubkey: snapshot, isSigner: false, isWritable: true },
+        { pubkey: payout, isSigner: false, isWritable: true },
+        { pubkey: paymentMint, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function claimSnapshot(vaultAddress: PublicKey, id: number, paymentMint: PublicKey, holderSnapshot: HolderSnapshot) {
+  const snapshot = await getSnapshotAddress(vaultAddress, id);
+  const payout = (await PublicKey.findProgramAddress([Buffer.from("snapshot_payout"), snapshot.toBuffer()], programId))[0];
+  const claim = (await PublicKey.findProgramAddress([Buffer.from("snapshot_claim"), snapshot.toBuffer(), payer.publicKey.toBuffer()], programId))[0];
+
+  const index = holderSnapshot.entries.findIndex(entry => entry.holder.equals(payer.publicKey));
+  if (index < 0) {
+    throw new Error(`${payer.publicKey.toBase58()} is not in the snapshot`);
+  }
+
+  const data = Buffer.from(borsh.serialize(
+    ClaimSnapshotSchema,
+    new ClaimSnapshotArgs({ balance: holderSnapshot.entries[index].balance, proof: holderSnapshot.tree.proof(index) })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, paymentMint), isSigner: false, isWritable: true },
+        { pubkey: snapshot, isSigner: false, isWritable: false },
+        { pubkey: claim, isSigner: false, isWritable: true },
+        { pubkey: payout, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+
+/*************************************************
+ *  Tokr Instructions - Shareholder Governance
+ *************************************************/
+export type ProposalAction =
+  | { kind: 'combineVault' }
+  | { kind: 'updateExternalPrice', pricePerShare: number, allowedToCombine: boolean };
+
+export async function getGovernanceAddress(vaultAddress: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("governance"), vaultAddress.toBuffer()], programId))[0];
+}
+
+export async function getProposalAccounts(vaultAddress: PublicKey, index: number) {
+  const governance = await getGovernanceAddress(vaultAddress);
+  const proposal = (await PublicKey.findProgramAddress([Buffer.from("proposal"), governance.toBuffer(), new BN(index).toArrayLike(Buffer, 'le', 8)], programId))[0];
+  const voteEscrow = (await PublicKey.findProgramAddress([Buffer.from("vote_escrow"), proposal.toBuffer()], programId))[0];
+  return { governance, proposal, voteEscrow };
+}
+
+export async function createGovernance(vaultAddress: PublicKey, quorumBps: number, thresholdBps: number, votingPeriod: number) {
+  const data = Buffer.from(borsh.serialize(
+    GovernanceSchema,
+    new GovernanceArgs({ quorum_bps: quorumBps, threshold_bps: thresholdBps, voting_period: votingPeriod })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: true },
+        { pubkey: await getGovernanceAddress(vaultAddress), isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_VAULT_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Open a proposal and return its index, which later governance calls use to find it
+ */
+export async function createProposal(vaultAddress: PublicKey, action: ProposalAction): Promise<number> {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const governance = await getGovernanceAddress(vaultAddress);
+  const governanceInfo = await connection.getAccountInfo(governance);
+  if (governanceInfo === null) {
+    throw new Error(`Vault ${vaultAddress.toBase58()} has no governance`);
+  }
+  // proposal_count follows key, vault, authority, fraction_mint, quorum_bps, threshold_bps and voting_period
+  const index = new BN(governanceInfo.data.slice(109, 117), 'le').toNumber();
+  const { proposal, voteEscrow } = await getProposalAccounts(vaultAddress, index);
+
+  const data = action.kind === 'combineVault'
+    ? Buffer.from(borsh.serialize(CombineVaultProposalSchema, new CombineVaultProposalArgs()))
+    : Buffer.from(borsh.serialize(
+      UpdatePriceProposalSchema,
+      new UpdatePriceProposalArgs({ price_per_share: action.pricePerShare, allowed_to_combine: action.allowedToCombine ? 1 : 0 })
+    ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: governance, isSigner: false, isWritable: true },
+        { pubkey: proposal, isSigner: false, isWritable: true },
+        { pubkey: voteEscrow, isSigner: false, isWritable: true },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: false },
+        { pubkey: (await getOfferingAccounts(vaultAddress)).offeringShares, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log(`Proposal ${index}: ${proposal.toBase58()}`);
+  console.log("Tx: ", tx);
+  return index;
+}
+
+export async function castVote(vaultAddress: PublicKey, index: number, approve: boolean, amount: number) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const { proposal, voteEscrow } = await getProposalAccounts(vaultAddress, index);
+  const vote = (await PublicKey.findProgramAddress([Buffer.from("vote"), proposal.toBuffer(), payer.publicKey.toBuffer()], programId))[0];
+
+  const data = Buffer.from(borsh.serialize(
+    CastVoteSchema,
+    new CastVoteArgs({ approve: approve ? 1 : 0, amount })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: proposal, isSigner: false, isWritable: true },
+        { pubkey: vote, isSigner: false, isWritable: true },
+        { pubkey: voteEscrow, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: await getGovernanceAddress(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function finalizeProposal(vaultAddress: PublicKey, index: number) {
+  const { governance, proposal } = await getProposalAccounts(vaultAddress, index);
+
+  const data = Buffer.from(borsh.serialize(
+    ProposalInstructionSchema,
+    new ProposalInstructionArgs({ instruction: 26 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: governance, isSigner: false, isWritable: false },
+        { pubkey: proposal, isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function withdrawVote(vaultAddress: PublicKey, index: number) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const { proposal, voteEscrow } = await getProposalAccounts(vaultAddress, index);
+  const vote = (await PublicKey.findProgramAddress([Buffer.from("vote"), proposal.toBuffer(), payer.publicKey.toBuffer()], programId))[0];
+
+  const data = Buffer.from(borsh.serialize(
+    ProposalInstructionSchema,
+    new ProposalInstructionArgs({ instruction: 27 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: proposal, isSigner: false, isWritable: false },
+        { pubkey: vote, isSigner: false, isWritable: true },
+        { pubkey: voteEscrow, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: await getGovernanceAddress(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Execute a passed proposal, signed by the vault authority.
+ * For a Combine the payer is also the buyer, paying from its wrapped SOL account.
+ */
+/**
+ * Close a defeated or executed proposal once every vote is withdrawn, returning its rent to the proposer
+ */
+export async function closeProposal(vaultAddress: PublicKey, index: number) {
+  const { proposal, voteEscrow } = await getProposalAccounts(vaultAddress, index);
+  const proposalInfo = await connection.getAccountInfo(proposal);
+  if (proposalInfo === null) {
+    throw new Error(`Proposal ${index} of vault ${vaultAddress.toBase58()} does not exist`);
+  }
+  // proposer follows key and governance
+  const proposer = new PublicKey(proposalInfo.data.slice(33, 65));
+
+  const data = Buffer.from(borsh.serialize(
+    ProposalInstructionSchema,
+    new ProposalInstructionArgs({ instruction: 56 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: proposer, isSigner: false, isWritable: true },
+        { pubkey: proposal, isSigner: false, isWritable: true },
+        { pubkey: voteEscrow, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function executeProposal(vaultAddress: PublicKey, index: number, action: ProposalAction, vaultCreator: PublicKey) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const { governance, proposal } = await getProposalAccounts(vaultAddress, index);
+
+  const data = Buffer.from(borsh.serialize(
+    ProposalInstructionSchema,
+    new ProposalInstructionArgs({ instruction: 28 })
+  ));
+
+  const keys = [
+    { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+    { pubkey: governance, isSigner: false, isWritable: false },
+    { pubkey: proposal, isSigner: false, isWritable: true },
+    { pubkey: vaultAddress, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_VAULT_PROGRAM_ID, isSigner: false, isWritable: false },
+    { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+    { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+  ];
+
+  if (action.kind === 'updateExternalPrice') {
+    const externalPricing = (await PublicKey.findProgramAddress([Buffer.from("external"), vaultAddress.toBuffer(), vaultCreator.toBuffer()], programId))[0];
+    keys.push(
+      { pubkey: externalPricing, isSigner: false, isWritable: true },
+      { pubkey: await getPortfolio(vaultAddress), isSigner: false, isWritable: false },
+    );
+  } else {
+    const fractionMint = new PublicKey(vault.data.fractionMint);
+    keys.push(
+      { pubkey: await getTokenWallet(payer.publicKey, fractionMint), isSigner: false, isWritable: true },
+      { pubkey: await getTokenWallet(payer.publicKey, NATIVE_MINT), isSigner: false, isWritable: true },
+      { pubkey: fractionMint, isSigner: false, isWritable: true },
+      { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: true },
+      { pubkey: new PublicKey(vault.data.redeemTreasury), isSigner: false, isWritable: true },
+      { pubkey: payer.publicKey, isSigner: false, isWritable: false },
+      { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+      { pubkey: await Vault.getPDA(vaultAddress), isSigner: false, isWritable: false },
+      { pubkey: new PublicKey(vault.data.pricingLookupAddress), isSigner: false, isWritable: false },
+      { pubkey: await getDeniedWalletAddress(payer.publicKey), isSigner: false, isWritable: false },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: await getFeeAccount(NATIVE_MINT), isSigner: false, isWritable: true },
+    );
+  }
+
+  const instruction = new TransactionInstruction(
+    {
+      keys,
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - KYC Allowlist
+ *************************************************/
+export async function getAllowlistAddress(vaultAddress: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("allowlist"), vaultAddress.toBuffer()], programId))[0];
+}
+
+/**
+ * The vault's allowlist and the wallet's entry in it. Both are passed to every instruction that sends shares to the wallet,
+ * whether or not the vault has an allowlist.
+ */
+export async function getAllowlistAccounts(vaultAddress: PublicKey, wallet: PublicKey) {
+  const allowlist = await getAllowlistAddress(vaultAddress);
+  const entry = (await PublicKey.findProgramAddress([Buffer.from("allowlist_entry"), allowlist.toBuffer(), wallet.toBuffer()], programId))[0];
+  return { allowlist, entry };
+}
+
+export async function createAllowlist(vaultAddress: PublicKey, complianceAuthority: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    CreateAllowlistSchema,
+    new CreateAllowlistArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: await getAllowlistAddress(vaultAddress), isSigner: false, isWritable: true },
+        { pubkey: complianceAuthority, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Allow a verified wallet to receive the vault's shares until `expiresTs` (unix seconds), signed by the compliance authority.
+ * `country` is the investor's ISO 3166-1 alpha-2 country of residence, checked against the vault's compliance profile.
+ */
+export async function setAllowlistEntry(vaultAddress: PublicKey, wallet: PublicKey, expiresTs: number, accredited = false, country = '') {
+  const { allowlist, entry } = await getAllowlistAccounts(vaultAddress, wallet);
+
+  const data = Buffer.from(borsh.serialize(
+    AllowlistEntrySchema,
+    new AllowlistEntryArgs({ expires_ts: expiresTs, accredited, country })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: entry, isSigner: false, isWritable: true },
+        { pubkey: wallet, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Compliance), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function removeAllowlistEntry(vaultAddress: PublicKey, wallet: PublicKey) {
+  const { allowlist, entry } = await getAllowlistAccounts(vaultAddress, wallet);
+
+  const data = Buffer.from(borsh.serialize(
+    RemoveAllowlistEntrySchema,
+    new RemoveAllowlistEntryArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: entry, isSigner: false, isWritable: true },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Compliance), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Transfer Shares
+ *************************************************/
+/**
+ * Send shares to another allowlisted holder
+ */
+export async function transferShares(vaultAddress: PublicKey, destination: PublicKey, amount: number) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const fractionMint = new PublicKey(vault.data.fractionMint);
+  const { allowlist, entry: senderEntry } = await getAllowlistAccounts(vaultAddress, payer.publicKey);
+  const destinationEntry = (await getAllowlistAccounts(vaultAddress, destination)).entry;
+  const { vaultCompliance, complianceProfile } = await getComplianceAccounts(vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    TransferSharesSchema,
+    new TransferSharesArgs({ number_of_shares: amount })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getTokenWallet(payer.publicKey, fractionMint), isSigner: false, isWritable: true },
+        { pubkey: destination, isSigner: false, isWritable: false },
+        { pubkey: await getTokenWallet(destination, fractionMint), isSigner: false, isWritable: true },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: fractionMint, isSigner: false, isWritable: false },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: senderEntry, isSigner: false, isWritable: false },
+        { pubkey: destinationEntry, isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(fractionMint), isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: vaultCompliance, isSigner: false, isWritable: false },
+        { pubkey: complianceProfile, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(payer.publicKey), isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(destination), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Vesting
+ *************************************************/
+export async function getVestingAccounts(vaultAddress: PublicKey, recipient: PublicKey) {
+  const vesting = (await PublicKey.findProgramAddress([Buffer.from("vesting"), vaultAddress.toBuffer(), recipient.toBuffer()], programId))[0];
+  const vestingShares = (await PublicKey.findProgramAddress([Buffer.from("vesting_shares"), vesting.toBuffer()], programId))[0];
+  return { vesting, vestingShares };
+}
+
+/**
+ * Release a recipient's vested shares. Anyone can crank this, the shares always go to the recipient.
+ */
+export async function claimVested(vaultAddress: PublicKey, recipient: PublicKey) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const fractionMint = new PublicKey(vault.data.fractionMint);
+  const { vesting, vestingShares } = await getVestingAccounts(vaultAddress, recipient);
+  const { allowlist, entry } = await getAllowlistAccounts(vaultAddress, recipient);
+  const { vaultCompliance, complianceProfile } = await getComplianceAccounts(vaultAddress);
+
+  const vestingInfo = await connection.getAccountInfo(vesting);
+  if (vestingInfo === null) {
+    throw new Error(`${recipient.toBase58()} has no vesting shares of vault ${vaultAddress.toBase58()}`);
+  }
+  // payer follows key, vault, recipient and fraction_mint
+  const rentReceiver = new PublicKey(vestingInfo.data.slice(97, 129));
+
+  const data = Buffer.from(borsh.serialize(
+    ClaimVestedSchema,
+    new ClaimVestedArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: recipient, isSigner: false, isWritable: false },
+        { pubkey: await getTokenWallet(recipient, fractionMint), isSigner: false, isWritable: true },
+        { pubkey: vesting, isSigner: false, isWritable: true },
+        { pubkey: vestingShares, isSigner: false, isWritable: true },
+        { pubkey: fractionMint, isSigner: false, isWritable: false },
+        { pubkey: rentReceiver, isSigner: false, isWritable: true },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: entry, isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(fractionMint), isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: vaultCompliance, isSigner: false, isWritable: false },
+        { pubkey: complianceProfile, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(recipient), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Holder Limit
+ *************************************************/
+export async function getHolderRegistry(fractionMint: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("holders"), fractionMint.toBuffer()], programId))[0];
+}
+
+/**
+ * Start counting the vault's holders, capped at `maxHolders` (0 for no limit).
+ * A vault that already issued shares passes how many holders it has now.
+ */
+export async function createHolderRegistry(vaultAddress: PublicKey, maxHolders: number) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    HolderRegistrySchema,
+    new HolderRegistryArgs({ max_holders: maxHolders })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionMint), isSigner: false, isWritable: false },
+        { pubkey: new PublicKey(vault.data.fractionTreasury), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function setMaxHolders(vaultAddress: PublicKey, maxHolders: number) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    AmountSchema,
+    new AmountArgs({ instruction: 35, amount: maxHolders })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(new PublicKey(vault.data.fractionMint)), isSigner: false, isWritable: true },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Compliance Profiles
+ *************************************************/
+export type ComplianceProfileRules = {
+  maxHolders?: number,
+  accreditedOnly?: boolean,
+  lockupDuration?: number,
+  allowedCountries?: string[],
+  transferRestriction?: TransferRestriction,
+};
+
+export async function getComplianceProfileAddress(authority: PublicKey, id: number) {
+  return (await PublicKey.findProgramAddress([Buffer.from("compliance_profile"), authority.toBuffer(), new BN(id).toArrayLike(Buffer, 'le', 8)], programId))[0];
+}
+
+export async function getVaultComplianceAddress(vaultAddress: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("vault_compliance"), vaultAddress.toBuffer()], programId))[0];
+}
+
+/**
+ * The vault compliance account and the profile it links to, as taken by every share-moving instruction.
+ * A vault without a profile passes its empty vault compliance account for both.
+ */
+export async function getComplianceAccounts(vaultAddress: PublicKey) {
+  const vaultCompliance = await getVaultComplianceAddress(vaultAddress);
+  const info = await connection.getAccountInfo(vaultCompliance);
+  // profile follows key and vault
+  const complianceProfile = info === null ? vaultCompliance : new PublicKey(info.data.slice(33, 65));
+  return { vaultCompliance, complianceProfile };
+}
+
+async function getVaultComplianceKeys(vaultAddress: PublicKey, complianceProfile?: PublicKey) {
+  if (!complianceProfile) {
+    return [];
+  }
+  return [
+    { pubkey: complianceProfile, isSigner: false, isWritable: false },
+    { pubkey: await getVaultComplianceAddress(vaultAddress), isSigner: false, isWritable: true },
+  ];
+}
+
+function complianceProfileData(instruction: number, id: number, rules: ComplianceProfileRules) {
+  return Buffer.from(borsh.serialize(
+    ComplianceProfileSchema,
+    new ComplianceProfileArgs({
+      instruction,
+      id,
+      max_holders: rules.maxHolders,
+      accredited_only: rules.accreditedOnly,
+      lockup_duration: rules.lockupDuration,
+      allowed_countries: rules.allowedCountries,
+      transfer_restriction: rules.transferRestriction,
+    })
+  ));
+}
+
+/**
+ * Create a profile owned by the payer, which vaults can then follow by passing it to createVault or tokenize
+ */
+export async function createComplianceProfile(id: number, rules: ComplianceProfileRules): Promise<PublicKey> {
+  const profile = await getComplianceProfileAddress(payer.publicKey, id);
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: profile, isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Compliance), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data: complianceProfileData(36, id, rules)
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Compliance profile:", profile.toBase58());
+  console.log("Tx: ", tx);
+  return profile;
+}
+
+/**
+ * Replace the rules of one of the payer's profiles, for every vault following it
+ */
+export async function updateComplianceProfile(id: number, rules: ComplianceProfileRules) {
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: await getComplianceProfileAddress(payer.publicKey, id), isSigner: false, isWritable: true },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data: complianceProfileData(37, id, rules)
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - KYC Attestations
+ *************************************************/
+
+/**
+ * Accept KYC attestations signed by `attester` in place of allowlist entries, signed by the compliance authority.
+ * Pass SystemProgram.programId to stop accepting them.
+ */
+export async function setAttester(vaultAddress: PublicKey, attester: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    SetAttesterSchema,
+    new SetAttesterArgs()
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: await getAllowlistAddress(vaultAddress), isSigner: false, isWritable: true },
+        { pubkey: attester, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Compliance), isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * The message the attester signs to verify `wallet` for the vault until `expiresTs` (unix seconds)
+ */
+export function getKycAttestationMessage(vaultAddress: PublicKey, wallet: PublicKey, expiresTs: number, accredited = false, country = '') {
+  return Buffer.from(borsh.serialize(
+    KycAttestationSchema,
+    new KycAttestation({ vault: vaultAddress.toBuffer(), wallet: wallet.toBuffer(), expires_ts: expiresTs, accredited, country })
+  ));
+}
+
+/**
+ * The ed25519 program instruction that verifies the attester's signature of an attestation,
+ * to pass to sendShare, buyShares or claimPurchase
+ */
+export function createKycAttestationInstruction(attester: PublicKey, message: Buffer, signature: Uint8Array) {
+  return Ed25519Program.createInstructionWithPublicKey({ publicKey: attester.toBytes(), message, signature });
+}
+
+/*************************************************
+ *  Tokr Instructions - Deny List
+ *************************************************/
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+export async function getDenyListAddress() {
+  return (await PublicKey.findProgramAddress([Buffer.from("deny_list")], programId))[0];
+}
+
+export async function getDeniedWalletAddress(wallet: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("denied"), wallet.toBuffer()], programId))[0];
+}
+
+/**
+ * Set up the sanctions deny list, signed by the payer as the program's upgrade authority
+ */
+export async function createDenyList(complianceAuthority: PublicKey) {
+  const programData = (await PublicKey.findProgramAddress([programId.toBuffer()], BPF_LOADER_UPGRADEABLE_PROGRAM_ID))[0];
+
+  const data = Buffer.from(borsh.serialize(
+    DenyListInstructionSchema,
+    new DenyListInstructionArgs({ instruction: 39 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: programData, isSigner: false, isWritable: false },
+        { pubkey: await getDenyListAddress(), isSigner: false, isWritable: true },
+        { pubkey: complianceAuthority, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Block a wallet from moving shares and rNFTs through Tokrizer, signed by the compliance authority
+ */
+export async function denyWallet(wallet: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    DenyListInstructionSchema,
+    new DenyListInstructionArgs({ instruction: 40 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getDenyListAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(wallet), isSigner: false, isWritable: true },
+        { pubkey: wallet, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Compliance), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function undenyWallet(wallet: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    DenyListInstructionSchema,
+    new DenyListInstructionArgs({ instruction: 41 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getDenyListAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(wallet), isSigner: false, isWritable: true },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Compliance), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Forced Transfers
+ *************************************************/
+export async function getClawbackAuthority(mint: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("clawback"), mint.toBuffer()], programId))[0];
+}
+
+/**
+ * Move shares or an rNFT from a holder to the destination without the holder's signature. Signed by the payer
+ * as the deny list compliance authority together with the program's upgrade authority. The reason hash is
+ * logged with the transfer, e.g. the sha256 of the court order.
+ */
+export async function forceTransfer(vaultAddress: PublicKey, mint: PublicKey, holder: PublicKey, destination: PublicKey, amount: number, reasonHash: Buffer, upgradeAuthority: Keypair) {
+  const programData = (await PublicKey.findProgramAddress([programId.toBuffer()], BPF_LOADER_UPGRADEABLE_PROGRAM_ID))[0];
+
+  const data = Buffer.from(borsh.serialize(
+    ForceTransferSchema,
+    new ForceTransferArgs({ amount, reason_hash: reasonHash })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getDenyListAddress(), isSigner: false, isWritable: false },
+        { pubkey: upgradeAuthority.publicKey, isSigner: true, isWritable: false },
+        { pubkey: programData, isSigner: false, isWritable: false },
+        { pubkey: await getTokenWallet(holder, mint), isSigner: false, isWritable: true },
+        { pubkey: destination, isSigner: false, isWritable: false },
+        { pubkey: await getTokenWallet(destination, mint), isSigner: false, isWritable: true },
+        { pubkey: await getDeniedWalletAddress(destination), isSigner: false, isWritable: false },
+        { pubkey: mint, isSigner: false, isWritable: false },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: await getPortfolio(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getClawbackAuthority(mint), isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(mint), isSigner: false, isWritable: true },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+        { pubkey: await getRoleAddress(payer.publicKey, Role.Compliance), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer, upgradeAuthority],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Delegate the payer's shares or rNFT of a mint to Tokrizer's clawback authority, so they can be force transferred
+ */
+export async function approveClawback(mint: PublicKey) {
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(await createApproveClawbackInstruction(mint)),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+async function createApproveClawbackInstruction(mint: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    ApproveClawbackSchema,
+    new ApproveClawbackArgs()
+  ));
+
+  return new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: await getTokenWallet(payer.publicKey, mint), isSigner: false, isWritable: true },
+        { pubkey: mint, isSigner: false, isWritable: false },
+        { pubkey: await getClawbackAuthority(mint), isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+}
+
+/*************************************************
+ *  Tokr Instructions - Wallet Recovery
+ *************************************************/
+export async function getRecoveryAddress(holder: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("recovery"), holder.toBuffer()], programId))[0];
+}
+
+/**
+ * Register the payer's recovery key, with the delay in seconds they have to cancel a recovery (at least 2 days).
+ * Shares are recovered through the clawback authority, so the same transaction approves the clawback of the payer's
+ * shares of each fraction mint in `fractionMints`. Shares of any other vault can't be recovered.
+ */
+export async function setRecoveryKey(recoveryKey: PublicKey, delay: number, fractionMints: PublicKey[]) {
+  const data = Buffer.from(borsh.serialize(
+    RecoverySchema,
+    new RecoveryArgs({ delay })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getRecoveryAddress(payer.publicKey), isSigner: false, isWritable: true },
+        { pubkey: recoveryKey, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const transaction = new Transaction().add(instruction);
+  for (const fractionMint of fractionMints) {
+    transaction.add(await createApproveClawbackInstruction(fractionMint));
+  }
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    transaction,
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Request the recovery of the holder's shares to a new wallet, signed by the payer as the recovery key
+ */
+export async function requestRecovery(holder: PublicKey, newWallet: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    RecoveryInstructionSchema,
+    new RecoveryInstructionArgs({ instruction: 45 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: await getRecoveryAddress(holder), isSigner: false, isWritable: true },
+        { pubkey: newWallet, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Cancel a requested recovery, signed by the payer as either the holder or the recovery key
+ */
+export async function cancelRecovery(holder: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    RecoveryInstructionSchema,
+    new RecoveryInstructionArgs({ instruction: 46 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: await getRecoveryAddress(holder), isSigner: false, isWritable: true },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Move the holder's shares of a vault to the new wallet once the recovery delay has passed,
+ * signed by the payer as the recovery key
+ */
+export async function executeRecovery(vaultAddress: PublicKey, holder: PublicKey, newWallet: PublicKey) {
+  const vault = await programs.vault.Vault.load(connection, vaultAddress);
+  const fractionMint = new PublicKey(vault.data.fractionMint);
+  const { allowlist, entry } = await getAllowlistAccounts(vaultAddress, newWallet);
+  const { vaultCompliance, complianceProfile } = await getComplianceAccounts(vaultAddress);
+
+  const data = Buffer.from(borsh.serialize(
+    RecoveryInstructionSchema,
+    new RecoveryInstructionArgs({ instruction: 47 })
+  ));
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getRecoveryAddress(holder), isSigner: false, isWritable: false },
+        { pubkey: await getTokenWallet(holder, fractionMint), isSigner: false, isWritable: true },
+        { pubkey: newWallet, isSigner: false, isWritable: false },
+        { pubkey: await getTokenWallet(newWallet, fractionMint), isSigner: false, isWritable: true },
+        { pubkey: vaultAddress, isSigner: false, isWritable: false },
+        { pubkey: fractionMint, isSigner: false, isWritable: false },
+        { pubkey: allowlist, isSigner: false, isWritable: false },
+        { pubkey: entry, isSigner: false, isWritable: false },
+        { pubkey: await getClawbackAuthority(fractionMint), isSigner: false, isWritable: false },
+        { pubkey: await getHolderRegistry(fractionMint), isSigner: false, isWritable: true },
+        { pubkey: vaultCompliance, isSigner: false, isWritable: false },
+        { pubkey: complianceProfile, isSigner: false, isWritable: false },
+        { pubkey: await getDeniedWalletAddress(newWallet), isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+      ],
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Config
+ *************************************************/
+export async function getConfigAddress() {
+  return (await PublicKey.findProgramAddress([Buffer.from("config")], programId))[0];
+}
+
+export type ConfigSettings = {
+  admin: PublicKey,
+  treasury: PublicKey,
+  mintFee: number,
+  vaultFee: number,
+  saleFeeBps: number,
+  metadataProgram?: PublicKey,
+  vaultProgram?: PublicKey,
+};
+
+function configData(instruction: number, settings: ConfigSettings) {
+  return Buffer.from(borsh.serialize(
+    ConfigSchema,
+    new ConfigArgs({
+      instruction,
+      admin: settings.admin.toBuffer(),
+      treasury: settings.treasury.toBuffer(),
+      mint_fee: settings.mintFee,
+      vault_fee: settings.vaultFee,
+      sale_fee_bps: settings.saleFeeBps,
+      metadata_program: (settings.metadataProgram ?? TOKEN_METADATA_PROGRAM_ID).toBuffer(),
+      vault_program: (settings.vaultProgram ?? TOKEN_VAULT_PROGRAM_ID).toBuffer(),
+    })
+  ));
+}
+
+/**
+ * Create the program's config, signed by the payer as the program's upgrade authority
+ */
+export async function initializeConfig(settings: ConfigSettings) {
+  const programData = (await PublicKey.findProgramAddress([programId.toBuffer()], BPF_LOADER_UPGRADEABLE_PROGRAM_ID))[0];
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: programData, isSigner: false, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: await getTreasuryAddress(), isSigner: false, isWritable: true },
+      ],
+      programId,
+      data: configData(48, settings)
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Replace the config's settings, signed by the payer as its admin
+ */
+export async function updateConfig(settings: ConfigSettings) {
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: await getConfigAddress(), isSigner: false, isWritable: true },
+      ],
+      programId,
+      data: configData(49, settings)
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Roles
+ *************************************************/
+export async function getRoleAddress(wallet: PublicKey, role: Role) {
+  return (await PublicKey.findProgramAddress([Buffer.from("role"), wallet.toBuffer(), Buffer.from([role])], programId))[0];
+}
+
+/**
+ * Grant (revoke = false) or revoke a role, signed by the payer as the config admin or a holder of the Admin role
+ */
+async function updateRole(wallet: PublicKey, role: Role, revoke: boolean) {
+  const data = Buffer.from(borsh.serialize(
+    RoleSchema,
+    new RoleArgs({ instruction: revoke ? 51 : 50, role })
+  ));
+
+  const keys = [
+    { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+    { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+    { pubkey: await getRoleAddress(payer.publicKey, Role.Admin), isSigner: false, isWritable: false },
+    { pubkey: await getRoleAddress(wallet, role), isSigner: false, isWritable: true },
+    { pubkey: wallet, isSigner: false, isWritable: false },
+  ];
+  if (!revoke) {
+    keys.push({ pubkey: SystemProgram.programId, isSigner: false, isWritable: false });
+    keys.push({ pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false });
+  }
+
+  const instruction = new TransactionInstruction(
+    {
+      keys,
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function grantRole(wallet: PublicKey, role: Role) {
+  await updateRole(wallet, role, false);
+}
+
+export async function revokeRole(wallet: PublicKey, role: Role) {
+  await updateRole(wallet, role, true);
+}
+
+/*************************************************
+ *  Tokr Instructions - Protocol Fees
+ *************************************************/
+export async function getTreasuryAddress() {
+  return (await PublicKey.findProgramAddress([Buffer.from("treasury")], programId))[0];
+}
+
+/**
+ * The treasury's token account sale fees paid in the mint go to
+ */
+export async function getFeeAccount(mint: PublicKey) {
+  return getTokenWallet(await getTreasuryAddress(), mint);
+}
+
+/**
+ * Create the treasury's token account for a payment mint, needed before sale fees can be taken in it
+ */
+export async function createFeeAccount(mint: PublicKey) {
+  const treasury = await getTreasuryAddress();
+
+  const instruction = new TransactionInstruction(
+    {
+      keys: [
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: await getFeeAccount(mint), isSigner: false, isWritable: true },
+        { pubkey: treasury, isSigner: false, isWritable: false },
+        { pubkey: mint, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+      ],
+      programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+      data: Buffer.alloc(0)
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/**
+ * Withdraw the treasury's lamports, and its tokens of the mint if given, to the config's treasury wallet.
+ * Signed by the payer as the config admin.
+ */
+export async function withdrawFees(treasuryWallet: PublicKey, mint?: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    WithdrawFeesSchema,
+    new WithdrawFeesArgs()
+  ));
+
+  const keys = [
+    { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+    { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+    { pubkey: await getTreasuryAddress(), isSigner: false, isWritable: true },
+    { pubkey: treasuryWallet, isSigner: false, isWritable: true },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+  ];
+  if (mint) {
+    keys.push(
+      { pubkey: await getFeeAccount(mint), isSigner: false, isWritable: true },
+      { pubkey: await getTokenWallet(treasuryWallet, mint), isSigner: false, isWritable: true },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    );
+  }
+
+  const instruction = new TransactionInstruction(
+    {
+      keys,
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+/*************************************************
+ *  Tokr Instructions - Emergency Pause
+ *************************************************/
+
+/**
+ * Pause (or unpause) the whole program, or only the vault if one is given, signed by the payer as the
+ * config admin or a holder of the Emergency role
+ */
+async function updatePause(paused: boolean, vaultAddress?: PublicKey) {
+  const data = Buffer.from(borsh.serialize(
+    PauseSchema,
+    new PauseArgs({ instruction: vaultAddress ? 54 : 53, paused })
+  ));
+
+  const keys = vaultAddress ? [
+    { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+    { pubkey: await getConfigAddress(), isSigner: false, isWritable: false },
+    { pubkey: await getRoleAddress(payer.publicKey, Role.Emergency), isSigner: false, isWritable: false },
+    { pubkey: vaultAddress, isSigner: false, isWritable: false },
+    { pubkey: await getVaultPause(vaultAddress), isSigner: false, isWritable: true },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+  ] : [
+    { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+    { pubkey: await getConfigAddress(), isSigner: false, isWritable: true },
+    { pubkey: await getRoleAddress(payer.publicKey, Role.Emergency), isSigner: false, isWritable: false },
+  ];
+
+  const instruction = new TransactionInstruction(
+    {
+      keys,
+      programId,
+      data
+    }
+  );
+
+  const tx = await sendAndConfirmTransaction(
+    connection,
+    new Transaction().add(instruction),
+    [payer],
+  );
+
+  console.log("Tx: ", tx);
+}
+
+export async function setPaused(paused: boolean) {
+  await updatePause(paused);
+}
+
+export async function setVaultPaused(vaultAddress: PublicKey, paused: boolean) {
+  await updatePause(paused, vaultAddress);
+}
+
+export async function getVaultPause(vaultAddress: PublicKey) {
+  return (await PublicKey.findProgramAddress([Buffer.from("vault_pause"), vaultAddress.toBuffer()], programId))[0];
+}
+
+export const getTokenWallet = async function (
+  wallet: PublicKey,
+  mint: PublicKey,
+) {
+  return (
+    await PublicKey.findProgramAddress(
+      [wallet.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+  )[0];
+};
+
+export const getPortfolio = async function (
+  vault: PublicKey,
+) {
+  return (
+    await PublicKey.findProgramAddress(
+      [Buffer.from("portfolio"), vault.toBuffer()],
+      programId,
     )
   )[0];
 };
\ No newline at end of file
@@ -1,3 +1,5 @@
+import { BN } from '@project-serum/anchor';
+
 export class TokrizeArgs {
     instruction = 0;
     name: string;
@@ -35,10 +37,14 @@ export class TokrizeArgs {
     instruction = 1;
     vault_bump: number;
     vault_seed: string;
-    constructor(fields: { vault_bump: number, vault_seed: string } | undefined = undefined) {
+    allow_further_share_creation: number; // borsh bool, 0 or 1
+    fraction_decimals: number;
+    constructor(fields: { vault_bump: number, vault_seed: string, allow_further_share_creation?: boolean, fraction_decimals?: number } | undefined = undefined) {
       if (fields) {
         this.vault_bump = fields.vault_bump;
         this.vault_seed = fields.vault_seed;
+        this.allow_further_share_creation = fields.allow_further_share_creation ? 1 : 0;
+        this.fraction_decimals = fields.fraction_decimals ?? 0;
       }
     }
   }
@@ -49,7 +55,9 @@ export class TokrizeArgs {
       fields: [
         ['instruction', 'u8'],
         ['vault_bump', 'u8'],
-        ['vault_seed', 'string']
+        ['vault_seed', 'string'],
+        ['allow_further_share_creation', 'u8'],
+        ['fraction_decimals', 'u8']
       ]
     }],
   ]);
@@ -87,22 +95,869 @@ export class TokrizeArgs {
     }],
   ]);
 
+  // Unix timestamps, see VestingSchedule in state.rs
+  export class VestingSchedule {
+    start_ts: number;
+    cliff_ts: number;
+    end_ts: number;
+    constructor(fields: { start_ts: number, cliff_ts: number, end_ts: number } | undefined = undefined) {
+      if (fields) {
+        this.start_ts = fields.start_ts;
+        this.cliff_ts = fields.cliff_ts;
+        this.end_ts = fields.end_ts;
+      }
+    }
+  }
+
   export class SendFractionArgs {
     instruction = 4;
     number_of_shares: number;
-    constructor(fields: { number_of_shares: number } | undefined = undefined) {
+    vesting: VestingSchedule | null;
+    constructor(fields: { number_of_shares: number, vesting?: VestingSchedule } | undefined = undefined) {
       if (fields) {
         this.number_of_shares = fields.number_of_shares;
+        this.vesting = fields.vesting ?? null;
       }
     }
   }
   
-  export const SendFractionSchema = new Map([
+  export const SendFractionSchema = new Map<any, any>([
     [SendFractionArgs, {
       kind: 'struct',
       fields: [
         ['instruction', 'u8'],
         ['number_of_shares', 'u64'],
+        ['vesting', { kind: 'option', type: VestingSchedule }],
+      ]
+    }],
+    [VestingSchedule, {
+      kind: 'struct',
+      fields: [
+        ['start_ts', 'u64'],
+        ['cliff_ts', 'u64'],
+        ['end_ts', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class TokenizeArgs {
+    instruction = 5;
+    name: string;
+    symbol: string;
+    uri: string;
+    mint_bump: number;
+    mint_seed: string;
+    vault_bump: number;
+    vault_seed: string;
+    allow_further_share_creation: number; // borsh bool, 0 or 1
+    fraction_decimals: number;
+    number_of_shares: number;
+    constructor(fields: { name: string, symbol: string, uri: string, mint_bump: number, mint_seed: string, vault_bump: number, vault_seed: string, allow_further_share_creation?: boolean, fraction_decimals?: number, number_of_shares: number } | undefined = undefined) {
+      if (fields) {
+        this.name = fields.name;
+        this.symbol = fields.symbol;
+        this.uri = fields.uri;
+        this.mint_bump = fields.mint_bump;
+        this.mint_seed = fields.mint_seed;
+        this.vault_bump = fields.vault_bump;
+        this.vault_seed = fields.vault_seed;
+        this.allow_further_share_creation = fields.allow_further_share_creation ? 1 : 0;
+        this.fraction_decimals = fields.fraction_decimals ?? 0;
+        this.number_of_shares = fields.number_of_shares;
+      }
+    }
+  }
+
+  // MintArgs and VaultArgs are nested structs on chain, which borsh lays out the same as these flattened fields
+  export const TokenizeSchema = new Map([
+    [TokenizeArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['name', 'string'],
+        ['symbol', 'string'],
+        ['uri', 'string'],
+        ['mint_bump', 'u8'],
+        ['mint_seed', 'string'],
+        ['vault_bump', 'u8'],
+        ['vault_seed', 'string'],
+        ['allow_further_share_creation', 'u8'],
+        ['fraction_decimals', 'u8'],
+        ['number_of_shares', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class BurnSharesArgs {
+    instruction = 6;
+    number_of_shares: number;
+    constructor(fields: { number_of_shares: number } | undefined = undefined) {
+      if (fields) {
+        this.number_of_shares = fields.number_of_shares;
+      }
+    }
+  }
+
+  export const BurnSharesSchema = new Map([
+    [BurnSharesArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['number_of_shares', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class CloseVaultArgs {
+    instruction = 7;
+  }
+
+  export const CloseVaultSchema = new Map([
+    [CloseVaultArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class SendFractionBatchArgs {
+    instruction = 8;
+    amounts: BN[];
+    constructor(fields: { amounts: BN[] } | undefined = undefined) {
+      if (fields) {
+        this.amounts = fields.amounts;
+      }
+    }
+  }
+
+  export const SendFractionBatchSchema = new Map([
+    [SendFractionBatchArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['amounts', ['u64']],
+      ]
+    }],
+  ]);
+
+  export class OpenOfferingArgs {
+    instruction = 9;
+    price_per_share: number;
+    number_of_shares: number;
+    start_ts: number;
+    end_ts: number;
+    max_shares_per_wallet: number;
+    min_shares: number;
+    constructor(fields: { price_per_share: number, number_of_shares: number, start_ts: number, end_ts: number, max_shares_per_wallet: number, min_shares?: number } | undefined = undefined) {
+      if (fields) {
+        this.price_per_share = fields.price_per_share;
+        this.number_of_shares = fields.number_of_shares;
+        this.start_ts = fields.start_ts;
+        this.end_ts = fields.end_ts;
+        this.max_shares_per_wallet = fields.max_shares_per_wallet;
+        this.min_shares = fields.min_shares ?? 0;
+      }
+    }
+  }
+
+  // start_ts and end_ts are i64 on chain, timestamps are never negative so u64 serializes the same
+  export const OpenOfferingSchema = new Map([
+    [OpenOfferingArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['price_per_share', 'u64'],
+        ['number_of_shares', 'u64'],
+        ['start_ts', 'u64'],
+        ['end_ts', 'u64'],
+        ['max_shares_per_wallet', 'u64'],
+        ['min_shares', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class BuySharesArgs {
+    instruction = 10;
+    number_of_shares: number;
+    constructor(fields: { number_of_shares: number } | undefined = undefined) {
+      if (fields) {
+        this.number_of_shares = fields.number_of_shares;
+      }
+    }
+  }
+
+  export const BuySharesSchema = new Map([
+    [BuySharesArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['number_of_shares', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class CloseOfferingArgs {
+    instruction = 11;
+  }
+
+  export const CloseOfferingSchema = new Map([
+    [CloseOfferingArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class FinalizeOfferingArgs {
+    instruction = 12;
+  }
+
+  export const FinalizeOfferingSchema = new Map([
+    [FinalizeOfferingArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class ClaimPurchaseArgs {
+    instruction = 13;
+  }
+
+  export const ClaimPurchaseSchema = new Map([
+    [ClaimPurchaseArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class RefundPurchaseArgs {
+    instruction = 14;
+  }
+
+  export const RefundPurchaseSchema = new Map([
+    [RefundPurchaseArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class InitDistributionArgs {
+    instruction = 15;
+  }
+
+  export const InitDistributionSchema = new Map([
+    [InitDistributionArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  // Shared by DepositIncome (16), StakeShares (17), UnstakeShares (18), FundSnapshot (21) and SetMaxHolders (35)
+  export class AmountArgs {
+    instruction: number;
+    amount: number;
+    constructor(fields: { instruction: number, amount: number } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+        this.amount = fields.amount;
+      }
+    }
+  }
+
+  export const AmountSchema = new Map([
+    [AmountArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['amount', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class ClaimIncomeArgs {
+    instruction = 19;
+  }
+
+  export const ClaimIncomeSchema = new Map([
+    [ClaimIncomeArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class SnapshotArgs {
+    instruction = 20;
+    id: number;
+    slot: number;
+    merkle_root: Buffer;
+    total_shares: number;
+    constructor(fields: { id: number, slot: number, merkle_root: Buffer, total_shares: number } | undefined = undefined) {
+      if (fields) {
+        this.id = fields.id;
+        this.slot = fields.slot;
+        this.merkle_root = fields.merkle_root;
+        this.total_shares = fields.total_shares;
+      }
+    }
+  }
+
+  export const SnapshotSchema = new Map([
+    [SnapshotArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['id', 'u64'],
+        ['slot', 'u64'],
+        ['merkle_root', [32]],
+        ['total_shares', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class ClaimSnapshotArgs {
+    instruction = 22;
+    balance: number;
+    proof: Buffer[];
+    constructor(fields: { balance: number, proof: Buffer[] } | undefined = undefined) {
+      if (fields) {
+        this.balance = fields.balance;
+        this.proof = fields.proof;
+      }
+    }
+  }
+
+  export const ClaimSnapshotSchema = new Map([
+    [ClaimSnapshotArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['balance', 'u64'],
+        ['proof', [[32]]],
+      ]
+    }],
+  ]);
+
+  export class GovernanceArgs {
+    instruction = 23;
+    quorum_bps: number;
+    threshold_bps: number;
+    voting_period: number;
+    constructor(fields: { quorum_bps: number, threshold_bps: number, voting_period: number } | undefined = undefined) {
+      if (fields) {
+        this.quorum_bps = fields.quorum_bps;
+        this.threshold_bps = fields.threshold_bps;
+        this.voting_period = fields.voting_period;
+      }
+    }
+  }
+
+  export const GovernanceSchema = new Map([
+    [GovernanceArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['quorum_bps', 'u16'],
+        ['threshold_bps', 'u16'],
+        ['voting_period', 'u64'],
+      ]
+    }],
+  ]);
+
+  // CreateProposal (24) carries a ProposalAction enum, written out here as its variant byte and fields
+  export class CombineVaultProposalArgs {
+    instruction = 24;
+    action = 0;
+  }
+
+  export const CombineVaultProposalSchema = new Map([
+    [CombineVaultProposalArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['action', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class UpdatePriceProposalArgs {
+    instruction = 24;
+    action = 1;
+    price_per_share: number;
+    allowed_to_combine: number;
+    constructor(fields: { price_per_share: number, allowed_to_combine: number } | undefined = undefined) {
+      if (fields) {
+        this.price_per_share = fields.price_per_share;
+        this.allowed_to_combine = fields.allowed_to_combine;
+      }
+    }
+  }
+
+  export const UpdatePriceProposalSchema = new Map([
+    [UpdatePriceProposalArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['action', 'u8'],
+        ['price_per_share', 'u64'],
+        ['allowed_to_combine', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class CastVoteArgs {
+    instruction = 25;
+    approve: number;
+    amount: number;
+    constructor(fields: { approve: number, amount: number } | undefined = undefined) {
+      if (fields) {
+        this.approve = fields.approve;
+        this.amount = fields.amount;
+      }
+    }
+  }
+
+  export const CastVoteSchema = new Map([
+    [CastVoteArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['approve', 'u8'],
+        ['amount', 'u64'],
+      ]
+    }],
+  ]);
+
+  // Shared by FinalizeProposal (26), WithdrawVote (27), ExecuteProposal (28) and CloseProposal (56)
+  export class ProposalInstructionArgs {
+    instruction: number;
+    constructor(fields: { instruction: number } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+      }
+    }
+  }
+
+  export const ProposalInstructionSchema = new Map([
+    [ProposalInstructionArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class CreateAllowlistArgs {
+    instruction = 29;
+  }
+
+  export const CreateAllowlistSchema = new Map([
+    [CreateAllowlistArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class AllowlistEntryArgs {
+    instruction = 30;
+    expires_ts: number;
+    accredited: number; // borsh bool, 0 or 1
+    country: Buffer; // ISO 3166-1 alpha-2
+    constructor(fields: { expires_ts: number, accredited?: boolean, country?: string } | undefined = undefined) {
+      if (fields) {
+        this.expires_ts = fields.expires_ts;
+        this.accredited = fields.accredited ? 1 : 0;
+        this.country = Buffer.from((fields.country ?? '').padEnd(2, '\0'));
+      }
+    }
+  }
+
+  export const AllowlistEntrySchema = new Map([
+    [AllowlistEntryArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['expires_ts', 'u64'],
+        ['accredited', 'u8'],
+        ['country', [2]],
+      ]
+    }],
+  ]);
+
+  export class RemoveAllowlistEntryArgs {
+    instruction = 31;
+  }
+
+  export const RemoveAllowlistEntrySchema = new Map([
+    [RemoveAllowlistEntryArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class TransferSharesArgs {
+    instruction = 32;
+    number_of_shares: number;
+    constructor(fields: { number_of_shares: number } | undefined = undefined) {
+      if (fields) {
+        this.number_of_shares = fields.number_of_shares;
+      }
+    }
+  }
+
+  export const TransferSharesSchema = new Map([
+    [TransferSharesArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['number_of_shares', 'u64'],
+      ]
+    }],
+  ]);
+
+  export class ClaimVestedArgs {
+    instruction = 33;
+  }
+
+  export const ClaimVestedSchema = new Map([
+    [ClaimVestedArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class HolderRegistryArgs {
+    instruction = 34;
+    max_holders: number;
+    constructor(fields: { max_holders: number } | undefined = undefined) {
+      if (fields) {
+        this.max_holders = fields.max_holders;
+      }
+    }
+  }
+
+  export const HolderRegistrySchema = new Map([
+    [HolderRegistryArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['max_holders', 'u64'],
+      ]
+    }],
+  ]);
+
+  export enum TransferRestriction {
+    Open,
+    Allowlist,
+  }
+
+  // Shared by CreateComplianceProfile (36) and UpdateComplianceProfile (37)
+  export class ComplianceProfileArgs {
+    instruction: number;
+    id: number;
+    max_holders: number;
+    accredited_only: number; // borsh bool, 0 or 1
+    lockup_duration: number;
+    allowed_countries: Buffer[];
+    transfer_restriction: number;
+    constructor(fields: { instruction: number, id: number, max_holders?: number, accredited_only?: boolean, lockup_duration?: number, allowed_countries?: string[], transfer_restriction?: TransferRestriction } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+        this.id = fields.id;
+        this.max_holders = fields.max_holders ?? 0;
+        this.accredited_only = fields.accredited_only ? 1 : 0;
+        this.lockup_duration = fields.lockup_duration ?? 0;
+        this.allowed_countries = (fields.allowed_countries ?? []).map(country => Buffer.from(country));
+        this.transfer_restriction = fields.transfer_restriction ?? TransferRestriction.Open;
+      }
+    }
+  }
+
+  export const ComplianceProfileSchema = new Map([
+    [ComplianceProfileArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['id', 'u64'],
+        ['max_holders', 'u64'],
+        ['accredited_only', 'u8'],
+        ['lockup_duration', 'u64'],
+        ['allowed_countries', [[2]]],
+        ['transfer_restriction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class SetAttesterArgs {
+    instruction = 38;
+  }
+
+  export const SetAttesterSchema = new Map([
+    [SetAttesterArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  // The message a KYC provider signs off-chain, accepted in place of an allowlist entry
+  export class KycAttestation {
+    vault: Buffer;
+    wallet: Buffer;
+    expires_ts: number;
+    accredited: number; // borsh bool, 0 or 1
+    country: Buffer; // ISO 3166-1 alpha-2
+    constructor(fields: { vault: Buffer, wallet: Buffer, expires_ts: number, accredited?: boolean, country?: string } | undefined = undefined) {
+      if (fields) {
+        this.vault = fields.vault;
+        this.wallet = fields.wallet;
+        this.expires_ts = fields.expires_ts;
+        this.accredited = fields.accredited ? 1 : 0;
+        this.country = Buffer.from((fields.country ?? '').padEnd(2, '\0'));
+      }
+    }
+  }
+
+  export const KycAttestationSchema = new Map([
+    [KycAttestation, {
+      kind: 'struct',
+      fields: [
+        ['vault', [32]],
+        ['wallet', [32]],
+        ['expires_ts', 'u64'],
+        ['accredited', 'u8'],
+        ['country', [2]],
+      ]
+    }],
+  ]);
+
+  // Shared by CreateDenyList (39), DenyWallet (40) and UndenyWallet (41)
+  export class DenyListInstructionArgs {
+    instruction: number;
+    constructor(fields: { instruction: number } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+      }
+    }
+  }
+
+  export const DenyListInstructionSchema = new Map([
+    [DenyListInstructionArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class ForceTransferArgs {
+    instruction = 42;
+    amount: number;
+    reason_hash: Buffer;
+    constructor(fields: { amount: number, reason_hash: Buffer } | undefined = undefined) {
+      if (fields) {
+        this.amount = fields.amount;
+        this.reason_hash = fields.reason_hash;
+      }
+    }
+  }
+
+  export const ForceTransferSchema = new Map([
+    [ForceTransferArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['amount', 'u64'],
+        ['reason_hash', [32]],
+      ]
+    }],
+  ]);
+
+  export class ApproveClawbackArgs {
+    instruction = 43;
+  }
+
+  export const ApproveClawbackSchema = new Map([
+    [ApproveClawbackArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class RecoveryArgs {
+    instruction = 44;
+    delay: number;
+    constructor(fields: { delay: number } | undefined = undefined) {
+      if (fields) {
+        this.delay = fields.delay;
+      }
+    }
+  }
+
+  export const RecoverySchema = new Map([
+    [RecoveryArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['delay', 'u64'],
+      ]
+    }],
+  ]);
+
+  // Shared by RequestRecovery (45), CancelRecovery (46) and ExecuteRecovery (47)
+  export class RecoveryInstructionArgs {
+    instruction: number;
+    constructor(fields: { instruction: number } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+      }
+    }
+  }
+
+  export const RecoveryInstructionSchema = new Map([
+    [RecoveryInstructionArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  // Shared by InitializeConfig (48) and UpdateConfig (49)
+  export class ConfigArgs {
+    instruction: number;
+    admin: Buffer;
+    treasury: Buffer;
+    mint_fee: number; // lamports
+    vault_fee: number; // lamports
+    sale_fee_bps: number;
+    metadata_program: Buffer;
+    vault_program: Buffer;
+    constructor(fields: { instruction: number, admin: Buffer, treasury: Buffer, mint_fee: number, vault_fee: number, sale_fee_bps: number, metadata_program: Buffer, vault_program: Buffer } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+        this.admin = fields.admin;
+        this.treasury = fields.treasury;
+        this.mint_fee = fields.mint_fee;
+        this.vault_fee = fields.vault_fee;
+        this.sale_fee_bps = fields.sale_fee_bps;
+        this.metadata_program = fields.metadata_program;
+        this.vault_program = fields.vault_program;
+      }
+    }
+  }
+
+  export const ConfigSchema = new Map([
+    [ConfigArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['admin', [32]],
+        ['treasury', [32]],
+        ['mint_fee', 'u64'],
+        ['vault_fee', 'u64'],
+        ['sale_fee_bps', 'u16'],
+        ['metadata_program', [32]],
+        ['vault_program', [32]],
+      ]
+    }],
+  ]);
+
+  // See Role in state.rs
+  export enum Role {
+    Admin,
+    Tokenizer,
+    PropertyManager,
+    Compliance,
+    Emergency,
+  }
+
+  // Shared by GrantRole (50) and RevokeRole (51)
+  export class RoleArgs {
+    instruction: number;
+    role: Role;
+    constructor(fields: { instruction: number, role: Role } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+        this.role = fields.role;
+      }
+    }
+  }
+
+  export const RoleSchema = new Map([
+    [RoleArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['role', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class WithdrawFeesArgs {
+    instruction = 52;
+  }
+
+  export const WithdrawFeesSchema = new Map([
+    [WithdrawFeesArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+      ]
+    }],
+  ]);
+
+  // Shared by SetPaused (53) and SetVaultPaused (54)
+  export class PauseArgs {
+    instruction: number;
+    paused: boolean;
+    constructor(fields: { instruction: number, paused: boolean } | undefined = undefined) {
+      if (fields) {
+        this.instruction = fields.instruction;
+        this.paused = fields.paused;
+      }
+    }
+  }
+
+  export const PauseSchema = new Map([
+    [PauseArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
+        ['paused', 'u8'],
+      ]
+    }],
+  ]);
+
+  export class ClosePurchaseArgs {
+    instruction = 55;
+  }
+
+  export const ClosePurchaseSchema = new Map([
+    [ClosePurchaseArgs, {
+      kind: 'struct',
+      fields: [
+        ['instruction', 'u8'],
       ]
     }],
   ]);
\ No newline at end of file
@@ -0,0 +1,21 @@
+use solana_program::program_error::ProgramError;
+
+/// Errors a client needs to tell apart from the generic ones, returned as `ProgramError::Custom(code)`
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokrizerError {
+    /// 0 - The wallet is on the sanctions deny list
+    WalletDenied,
+    /// 1 - The wallet does not hold the role the instruction requires
+    MissingRole,
+    /// 2 - The program is paused
+    ProgramPaused,
+    /// 3 - A vault the instruction works on is paused
+    VaultPaused,
+}
+
+impl From<TokrizerError> for ProgramError {
+    fn from(e: TokrizerError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
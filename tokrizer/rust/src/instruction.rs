@@ -1,4 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+use crate::state::{ProposalAction, Role, TransferRestriction, VestingSchedule};
 
 
 #[repr(C)]
@@ -15,7 +18,9 @@ pub struct MintArgs {
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct VaultArgs {
     pub vault_bump: u8,
-    pub vault_seed: String
+    pub vault_seed: String,
+    pub allow_further_share_creation: bool,
+    pub fraction_decimals: u8,
 }
 
 #[repr(C)]
@@ -28,6 +33,146 @@ pub struct FractionalizeArgs {
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
 pub struct SendShareArgs {
     pub number_of_shares: u64,
+    /// Lock the shares in a vesting escrow for the destination instead of sending them straight away
+    pub vesting: Option<VestingSchedule>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SendShareBatchArgs {
+    pub amounts: Vec<u64>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct TokenizeArgs {
+    pub mint: MintArgs,
+    pub vault: VaultArgs,
+    pub number_of_shares: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct BurnSharesArgs {
+    pub number_of_shares: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct OpenOfferingArgs {
+    pub price_per_share: u64,
+    pub number_of_shares: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub max_shares_per_wallet: u64,
+    pub min_shares: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct BuySharesArgs {
+    pub number_of_shares: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct AmountArgs {
+    pub amount: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SnapshotArgs {
+    pub id: u64,
+    pub slot: u64,
+    pub merkle_root: [u8; 32],
+    pub total_shares: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct ClaimSnapshotArgs {
+    pub balance: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct GovernanceArgs {
+    pub quorum_bps: u16,
+    pub threshold_bps: u16,
+    pub voting_period: i64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct CastVoteArgs {
+    pub approve: bool,
+    pub amount: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct AllowlistEntryArgs {
+    pub expires_ts: i64,
+    pub accredited: bool,
+    pub country: [u8; 2],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct HolderRegistryArgs {
+    pub max_holders: u64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct ComplianceProfileArgs {
+    pub id: u64,
+    pub max_holders: u64,
+    pub accredited_only: bool,
+    pub lockup_duration: i64,
+    pub allowed_countries: Vec<[u8; 2]>,
+    pub transfer_restriction: TransferRestriction,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct ForceTransferArgs {
+    pub amount: u64,
+    /// Hash of the court order or other reason, logged with the transfer
+    pub reason_hash: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct RecoveryArgs {
+    /// Seconds the holder has to cancel a recovery request, at least MIN_RECOVERY_DELAY
+    pub delay: i64,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct ConfigArgs {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub mint_fee: u64,
+    pub vault_fee: u64,
+    pub sale_fee_bps: u16,
+    pub metadata_program: Pubkey,
+    pub vault_program: Pubkey,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct RoleArgs {
+    pub role: Role,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct PauseArgs {
+    pub paused: bool,
 }
 
 #[repr(C)]
@@ -39,5 +184,57 @@ pub enum TokrizerInstruction {
     AddNftToVault,
     Fractionalize(FractionalizeArgs),
     SendShare(SendShareArgs),
+    Tokenize(TokenizeArgs),
+    BurnTreasuryShares(BurnSharesArgs),
+    CloseVault,
+    SendShareBatch(SendShareBatchArgs),
+    OpenOffering(OpenOfferingArgs),
+    BuyShares(BuySharesArgs),
+    CloseOffering,
+    FinalizeOffering,
+    ClaimPurchase,
+    RefundPurchase,
+    InitDistribution,
+    DepositIncome(AmountArgs),
+    StakeShares(AmountArgs),
+    UnstakeShares(AmountArgs),
+    ClaimIncome,
+    CreateSnapshot(SnapshotArgs),
+    FundSnapshot(AmountArgs),
+    ClaimSnapshot(ClaimSnapshotArgs),
+    CreateGovernance(GovernanceArgs),
+    CreateProposal(ProposalAction),
+    CastVote(CastVoteArgs),
+    FinalizeProposal,
+    WithdrawVote,
+    ExecuteProposal,
+    CreateAllowlist,
+    SetAllowlistEntry(AllowlistEntryArgs),
+    RemoveAllowlistEntry,
+    TransferShares(AmountArgs),
+    ClaimVested,
+    CreateHolderRegistry(HolderRegistryArgs),
+    SetMaxHolders(AmountArgs),
+    CreateComplianceProfile(ComplianceProfileArgs),
+    UpdateComplianceProfile(ComplianceProfileArgs),
+    SetAttester,
+    CreateDenyList,
+    DenyWallet,
+    UndenyWallet,
+    ForceTransfer(ForceTransferArgs),
+    ApproveClawback,
+    SetRecoveryKey(RecoveryArgs),
+    RequestRecovery,
+    CancelRecovery,
+    ExecuteRecovery,
+    InitializeConfig(ConfigArgs),
+    UpdateConfig(ConfigArgs),
+    GrantRole(RoleArgs),
+    RevokeRole(RoleArgs),
+    WithdrawFees,
+    SetPaused(PauseArgs),
+    SetVaultPaused(PauseArgs),
+    ClosePurchase,
+    CloseProposal,
 
 }
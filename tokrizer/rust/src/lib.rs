@@ -1,5 +1,8 @@
+pub mod error;
 pub mod processor;
 pub mod instruction;
+pub mod state;
+pub mod utils;
 
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
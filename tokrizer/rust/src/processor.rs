@@ -1,10 +1,14 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use std::convert::TryFrom;
 use mpl_token_metadata::{instruction::create_metadata_accounts_v2, state::{Creator, PREFIX as META_PREFIX}};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     program_error::ProgramError,
+    program_option::COption,
     instruction::{AccountMeta, Instruction},
+    clock::Clock,
+    hash::Hash,
     msg,
     program::{invoke, invoke_signed},
     program_pack::Pack,
@@ -15,22 +19,54 @@ use solana_program::{
 };
 use spl_token::{
     self,
-    instruction::{approve, initialize_account, initialize_mint, mint_to},
+    instruction::{approve, burn, close_account, initialize_account, initialize_mint, mint_to, transfer},
     state::{Account, Mint},
 };
 
 use mpl_token_vault::{
     instruction::{
-        create_activate_vault_instruction, create_init_vault_instruction,
-        create_mint_shares_instruction, create_update_external_price_account_instruction,
-        create_withdraw_shares_instruction, AmountArgs, VaultInstruction,
+        create_activate_vault_instruction, create_combine_vault_instruction,
+        create_init_vault_instruction, create_mint_shares_instruction, create_set_authority_instruction,
+        create_update_external_price_account_instruction, create_withdraw_shares_instruction,
+        AmountArgs, VaultInstruction,
     },
-    state::{Vault, VaultState, MAX_EXTERNAL_ACCOUNT_SIZE, MAX_VAULT_SIZE},
+    state::{ExternalPriceAccount, Vault, VaultState, MAX_EXTERNAL_ACCOUNT_SIZE, MAX_VAULT_SIZE},
 };
 
-use spl_associated_token_account::{create_associated_token_account};
+use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
 
-use crate::instruction::TokrizerInstruction;
+use crate::{
+    error::TokrizerError,
+    instruction::{
+        AllowlistEntryArgs, ComplianceProfileArgs, ConfigArgs, ForceTransferArgs, GovernanceArgs,
+        HolderRegistryArgs, OpenOfferingArgs, SnapshotArgs, TokenizeArgs, TokrizerInstruction,
+    },
+    state::{
+        Allowlist, AllowlistEntry, ComplianceProfile, Config, DeniedWallet, DenyList, Distribution,
+        Governance, HolderRegistry, HolderStake, Key, KycAttestation, Offering, OfferingNonce,
+        OfferingState, Portfolio, Proposal, ProposalAction, ProposalState, Purchase, Recovery, Role,
+        RoleAssignment, Snapshot, SnapshotClaim, VaultCompliance, VaultPause, Vesting,
+        VestingSchedule, VoteRecord, ALLOWLIST_ENTRY_PREFIX, ALLOWLIST_ENTRY_SIZE, ALLOWLIST_PREFIX,
+        ALLOWLIST_SIZE, CLAWBACK_PREFIX, COMPLIANCE_PROFILE_PREFIX, COMPLIANCE_PROFILE_SIZE,
+        CONFIG_PREFIX, CONFIG_SIZE, DENIED_WALLET_PREFIX, DENIED_WALLET_SIZE, DENY_LIST_PREFIX,
+        DENY_LIST_SIZE, DISTRIBUTION_PREFIX, DISTRIBUTION_SIZE, GOVERNANCE_PREFIX, GOVERNANCE_SIZE,
+        HOLDER_REGISTRY_PREFIX, HOLDER_REGISTRY_SIZE, HOLDER_STAKE_SIZE, INCOME_PER_SHARE_PRECISION,
+        INCOME_PREFIX, MAX_FEE_BPS, MAX_PORTFOLIO_MINTS, MAX_PORTFOLIO_SIZE, MAX_PROFILE_COUNTRIES,
+        MIN_RECOVERY_DELAY, OFFERING_NONCE_PREFIX, OFFERING_NONCE_SIZE, OFFERING_PREFIX,
+        OFFERING_SHARES_PREFIX, OFFERING_SIZE, PORTFOLIO_PREFIX, PROCEEDS_PREFIX, PROPOSAL_PREFIX,
+        PROPOSAL_SIZE, PURCHASE_PREFIX, PURCHASE_SIZE, RECOVERY_PREFIX, RECOVERY_SIZE, ROLE_PREFIX,
+        ROLE_SIZE, SNAPSHOT_CLAIM_PREFIX, SNAPSHOT_CLAIM_SIZE, SNAPSHOT_PAYOUT_PREFIX,
+        SNAPSHOT_PREFIX, SNAPSHOT_SIZE, STAKED_PREFIX, STAKE_PREFIX, TREASURY_PREFIX,
+        VAULT_COMPLIANCE_PREFIX, VAULT_COMPLIANCE_SIZE, VAULT_PAUSE_PREFIX, VAULT_PAUSE_SIZE,
+        VESTING_PREFIX, VESTING_SHARES_PREFIX, VESTING_SIZE, VOTE_ESCROW_PREFIX, VOTE_PREFIX,
+        VOTE_RECORD_SIZE,
+    },
+    utils::{
+        assert_derivation, assert_owned_by, assert_upgrade_authority, close_program_account,
+        create_program_account, create_token_account, ed25519_signed_messages, snapshot_leaf,
+        verify_merkle_proof,
+    },
+};
 
 pub fn process(
     program_id: &Pubkey,
@@ -39,6 +75,12 @@ pub fn process(
 ) -> ProgramResult {
     let instruction = TokrizerInstruction::try_from_slice(instruction_data)?;
 
+    // Every instruction takes the config account, so pausing the program stops all but the pause instructions
+    match instruction {
+        TokrizerInstruction::SetPaused(_) | TokrizerInstruction::SetVaultPaused(_) => {}
+        _ => assert_program_not_paused(program_id, find_config(program_id, accounts)?)?,
+    }
+
     match instruction {
         TokrizerInstruction::MintTokrNft(args) => {
             msg!(
@@ -59,7 +101,14 @@ pub fn process(
         }
         TokrizerInstruction::CreateVault(args) => {
             msg!("Create Vault Instruction!");
-            create_vault(program_id, accounts, args.vault_seed, args.vault_bump)
+            create_vault(
+                program_id,
+                accounts,
+                args.vault_seed,
+                args.vault_bump,
+                args.allow_further_share_creation,
+                args.fraction_decimals,
+            )
         }
         TokrizerInstruction::AddNftToVault => {
             msg!("Add NFT To Vault Instruction!");
@@ -74,7 +123,227 @@ pub fn process(
         }
         TokrizerInstruction::SendShare(args) => {
             msg!("Send Fraction {} Shares of rNFT", args.number_of_shares);
-            send_share(program_id, accounts, args.number_of_shares)
+            send_share(program_id, accounts, args.number_of_shares, args.vesting)
+        }
+        TokrizerInstruction::Tokenize(args) => {
+            msg!(
+                "Tokenize Instruction! Name: {}, NumberOfShares: {}",
+                args.mint.name,
+                args.number_of_shares
+            );
+            tokenize(program_id, accounts, args)
+        }
+        TokrizerInstruction::BurnTreasuryShares(args) => {
+            msg!("Burn {} Shares from the Fraction Treasury", args.number_of_shares);
+            burn_treasury_shares(program_id, accounts, args.number_of_shares)
+        }
+        TokrizerInstruction::SendShareBatch(args) => {
+            msg!("Send Fraction Shares of rNFT to {} Destinations", args.amounts.len());
+            send_share_batch(program_id, accounts, args.amounts)
+        }
+        TokrizerInstruction::OpenOffering(args) => {
+            msg!(
+                "Open Offering Instruction! {} Shares at {} each",
+                args.number_of_shares,
+                args.price_per_share
+            );
+            open_offering(program_id, accounts, args)
+        }
+        TokrizerInstruction::BuyShares(args) => {
+            msg!("Buy {} Shares Instruction!", args.number_of_shares);
+            buy_shares(program_id, accounts, args.number_of_shares)
+        }
+        TokrizerInstruction::CloseOffering => {
+            msg!("Close Offering Instruction!");
+            close_offering(program_id, accounts)
+        }
+        TokrizerInstruction::FinalizeOffering => {
+            msg!("Finalize Offering Instruction!");
+            finalize_offering(program_id, accounts)
+        }
+        TokrizerInstruction::ClaimPurchase => {
+            msg!("Claim Purchase Instruction!");
+            claim_purchase(program_id, accounts)
+        }
+        TokrizerInstruction::RefundPurchase => {
+            msg!("Refund Purchase Instruction!");
+            refund_purchase(program_id, accounts)
+        }
+        TokrizerInstruction::InitDistribution => {
+            msg!("Init Distribution Instruction!");
+            init_distribution(program_id, accounts)
+        }
+        TokrizerInstruction::DepositIncome(args) => {
+            msg!("Deposit {} Income Instruction!", args.amount);
+            deposit_income(program_id, accounts, args.amount)
+        }
+        TokrizerInstruction::StakeShares(args) => {
+            msg!("Stake {} Shares Instruction!", args.amount);
+            update_stake(program_id, accounts, args.amount, 0)
+        }
+        TokrizerInstruction::UnstakeShares(args) => {
+            msg!("Unstake {} Shares Instruction!", args.amount);
+            update_stake(program_id, accounts, 0, args.amount)
+        }
+        TokrizerInstruction::ClaimIncome => {
+            msg!("Claim Income Instruction!");
+            update_stake(program_id, accounts, 0, 0)
+        }
+        TokrizerInstruction::CreateSnapshot(args) => {
+            msg!("Create Snapshot {} at Slot {} Instruction!", args.id, args.slot);
+            create_snapshot(program_id, accounts, args)
+        }
+        TokrizerInstruction::FundSnapshot(args) => {
+            msg!("Fund Snapshot with {} Instruction!", args.amount);
+            fund_snapshot(program_id, accounts, args.amount)
+        }
+        TokrizerInstruction::ClaimSnapshot(args) => {
+            msg!("Claim Snapshot Instruction! Balance: {}", args.balance);
+            claim_snapshot(program_id, accounts, args.balance, args.proof)
+        }
+        TokrizerInstruction::CreateGovernance(args) => {
+            msg!(
+                "Create Governance Instruction! Quorum: {} bps, Threshold: {} bps",
+                args.quorum_bps,
+                args.threshold_bps
+            );
+            create_governance(program_id, accounts, args)
+        }
+        TokrizerInstruction::CreateProposal(action) => {
+            msg!("Create Proposal Instruction! {:?}", action);
+            create_proposal(program_id, accounts, action)
+        }
+        TokrizerInstruction::CastVote(args) => {
+            msg!("Cast Vote Instruction! Approve: {}, Shares: {}", args.approve, args.amount);
+            cast_vote(program_id, accounts, args.approve, args.amount)
+        }
+        TokrizerInstruction::FinalizeProposal => {
+            msg!("Finalize Proposal Instruction!");
+            finalize_proposal(program_id, accounts)
+        }
+        TokrizerInstruction::WithdrawVote => {
+            msg!("Withdraw Vote Instruction!");
+            withdraw_vote(program_id, accounts)
+        }
+        TokrizerInstruction::ExecuteProposal => {
+            msg!("Execute Proposal Instruction!");
+            execute_proposal(program_id, accounts)
+        }
+        TokrizerInstruction::CreateAllowlist => {
+            msg!("Create Allowlist Instruction!");
+            create_allowlist(program_id, accounts)
+        }
+        TokrizerInstruction::SetAllowlistEntry(args) => {
+            msg!("Set Allowlist Entry Instruction! Expires: {}", args.expires_ts);
+            set_allowlist_entry(program_id, accounts, args)
+        }
+        TokrizerInstruction::RemoveAllowlistEntry => {
+            msg!("Remove Allowlist Entry Instruction!");
+            remove_allowlist_entry(program_id, accounts)
+        }
+        TokrizerInstruction::TransferShares(args) => {
+            msg!("Transfer Shares Instruction! Shares: {}", args.amount);
+            transfer_shares(program_id, accounts, args.amount)
+        }
+        TokrizerInstruction::ClaimVested => {
+            msg!("Claim Vested Instruction!");
+            claim_vested(program_id, accounts)
+        }
+        TokrizerInstruction::CreateHolderRegistry(args) => {
+            msg!("Create Holder Registry Instruction! Max: {}", args.max_holders);
+            create_holder_registry(program_id, accounts, args)
+        }
+        TokrizerInstruction::SetMaxHolders(args) => {
+            msg!("Set Max Holders Instruction! Max: {}", args.amount);
+            set_max_holders(program_id, accounts, args.amount)
+        }
+        TokrizerInstruction::CreateComplianceProfile(args) => {
+            msg!("Create Compliance Profile Instruction! Id: {}", args.id);
+            create_compliance_profile(program_id, accounts, args)
+        }
+        TokrizerInstruction::UpdateComplianceProfile(args) => {
+            msg!("Update Compliance Profile Instruction! Id: {}", args.id);
+            update_compliance_profile(program_id, accounts, args)
+        }
+        TokrizerInstruction::SetAttester => {
+            msg!("Set Attester Instruction!");
+            set_attester(program_id, accounts)
+        }
+        TokrizerInstruction::CreateDenyList => {
+            msg!("Create Deny List Instruction!");
+            create_deny_list(program_id, accounts)
+        }
+        TokrizerInstruction::DenyWallet => {
+            msg!("Deny Wallet Instruction!");
+            deny_wallet(program_id, accounts)
+        }
+        TokrizerInstruction::UndenyWallet => {
+            msg!("Undeny Wallet Instruction!");
+            undeny_wallet(program_id, accounts)
+        }
+        TokrizerInstruction::ForceTransfer(args) => {
+            msg!("Force Transfer Instruction! Amount: {}", args.amount);
+            force_transfer(program_id, accounts, args)
+        }
+        TokrizerInstruction::ApproveClawback => {
+            msg!("Approve Clawback Instruction!");
+            approve_clawback(program_id, accounts)
+        }
+        TokrizerInstruction::SetRecoveryKey(args) => {
+            msg!("Set Recovery Key Instruction! Delay: {}", args.delay);
+            set_recovery_key(program_id, accounts, args.delay)
+        }
+        TokrizerInstruction::RequestRecovery => {
+            msg!("Request Recovery Instruction!");
+            request_recovery(program_id, accounts)
+        }
+        TokrizerInstruction::CancelRecovery => {
+            msg!("Cancel Recovery Instruction!");
+            cancel_recovery(program_id, accounts)
+        }
+        TokrizerInstruction::ExecuteRecovery => {
+            msg!("Execute Recovery Instruction!");
+            execute_recovery(program_id, accounts)
+        }
+        TokrizerInstruction::InitializeConfig(args) => {
+            msg!("Initialize Config Instruction!");
+            initialize_config(program_id, accounts, args)
+        }
+        TokrizerInstruction::UpdateConfig(args) => {
+            msg!("Update Config Instruction!");
+            update_config(program_id, accounts, args)
+        }
+        TokrizerInstruction::GrantRole(args) => {
+            msg!("Grant Role Instruction! Role: {:?}", args.role);
+            grant_role(program_id, accounts, args.role)
+        }
+        TokrizerInstruction::RevokeRole(args) => {
+            msg!("Revoke Role Instruction! Role: {:?}", args.role);
+            revoke_role(program_id, accounts, args.role)
+        }
+        TokrizerInstruction::WithdrawFees => {
+            msg!("Withdraw Fees Instruction!");
+            withdraw_fees(program_id, accounts)
+        }
+        TokrizerInstruction::SetPaused(args) => {
+            msg!("Set Paused Instruction! Paused: {}", args.paused);
+            set_paused(program_id, accounts, args.paused)
+        }
+        TokrizerInstruction::SetVaultPaused(args) => {
+            msg!("Set Vault Paused Instruction! Paused: {}", args.paused);
+            set_vault_paused(program_id, accounts, args.paused)
+        }
+        TokrizerInstruction::ClosePurchase => {
+            msg!("Close Purchase Instruction!");
+            close_purchase(program_id, accounts)
+        }
+        TokrizerInstruction::CloseProposal => {
+            msg!("Close Proposal Instruction!");
+            close_proposal(program_id, accounts)
+        }
+        TokrizerInstruction::CloseVault => {
+            msg!("Close Vault Instruction!");
+            close_vault(program_id, accounts)
         }
     }
 }
@@ -111,6 +380,31 @@ pub fn mint_nft(
 
     let rent_program = next_account_info(accounts_iter)?;
 
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let destination_denied = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let payer_role = next_account_info(accounts_iter)?;
+
+    let treasury = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, payer_role, payer.key, Role::Tokenizer)?;
+
+    assert_not_denied(program_id, destination.key, destination_denied)?;
+
+    let config = load_config(program_id, config_info)?;
+    if let Some(config) = &config {
+        if config.metadata_program != *metadata_program.key {
+            msg!("Metadata program is not the one allowed by the config");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+    }
+
+    let mint_fee = config.as_ref().map_or(0, |config| config.mint_fee);
+    charge_flat_fee(program_id, payer, treasury, system_program, mint_fee)?;
+
     // todo check if metadata input is correct
     let (mint_pda_key, mind_pda_bump) = Pubkey::find_program_address(
         &[
@@ -145,7 +439,7 @@ pub fn mint_nft(
             metadata_program.key.as_ref(),
             mint.key.as_ref(),
         ],
-        &metadata_program.key,
+        metadata_program.key,
     );
 
     if *metadata_account.key != metadata_pda_key {
@@ -204,7 +498,7 @@ pub fn mint_nft(
     let creator = Creator {
         address: *creator.key,
         verified: true,
-        share: 100 as u8,
+        share: 100,
     };
     invoke_signed(
         &create_metadata_accounts_v2(
@@ -235,8 +529,8 @@ pub fn mint_nft(
             mint.key,
             token_account.key,
             destination.key,
-            &[&payer.key],
-            1 as u64,
+            &[payer.key],
+            1,
         )?,
         accounts,
     )?;
@@ -249,6 +543,8 @@ pub fn create_vault(
     accounts: &[AccountInfo],
     vault_seed: String,
     vault_bump: u8,
+    allow_further_share_creation: bool,
+    fraction_decimals: u8,
 ) -> ProgramResult {
 
     let accounts_iter = &mut accounts.iter();
@@ -281,6 +577,25 @@ pub fn create_vault(
 
     let native_mint_program = next_account_info(accounts_iter)?;
 
+    let config_info = next_account_info(accounts_iter)?;
+
+    let payer_role = next_account_info(accounts_iter)?;
+
+    let treasury = next_account_info(accounts_iter)?;
+
+    // The vault can follow a Compliance Profile, which share-moving instructions consult from then on
+    let compliance_profile = next_account_info(accounts_iter).ok();
+
+    let vault_compliance = next_account_info(accounts_iter).ok();
+
+    assert_role(program_id, config_info, payer_role, payer.key, Role::Tokenizer)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+    let config = load_config(program_id, config_info)?;
+
+    let vault_fee = config.as_ref().map_or(0, |config| config.vault_fee);
+    charge_flat_fee(program_id, payer, treasury, system_program, vault_fee)?;
+
     let vault_signing_seeds = &[
         payer.key.as_ref(),
         token_vault_program.key.as_ref(),
@@ -290,7 +605,7 @@ pub fn create_vault(
 
     let (_external_pricing_pda, ebump) = Pubkey::find_program_address(
         &[b"external", vault.key.as_ref(), payer.key.as_ref()],
-        &program_id,
+        program_id,
     );
     let external_pricing_signing_seeds = &[
         b"external",
@@ -301,7 +616,7 @@ pub fn create_vault(
 
     let (_fraction_mint_pda, fbump) = Pubkey::find_program_address(
         &[b"fraction", vault.key.as_ref(), payer.key.as_ref()],
-        &program_id,
+        program_id,
     );
     let fraction_mint_signing_seeds = &[
         b"fraction",
@@ -329,7 +644,7 @@ pub fn create_vault(
         &create_update_external_price_account_instruction(
             *token_vault_program.key,
             *external_pricing_acct.key,
-            0 as u64, // todo Price, set this number if we want to give tokens a price
+            0, // todo Price, set this number if we want to give tokens a price
             spl_token::native_mint::ID,
             true,
         ),
@@ -357,7 +672,7 @@ pub fn create_vault(
             fraction_mint.key,
             vault_mint_authority.key,
             Some(vault_mint_authority.key),
-            0,
+            fraction_decimals,
         )?,
         accounts,
         &[fraction_mint_signing_seeds],
@@ -422,12 +737,39 @@ pub fn create_vault(
             *vault.key,
             *vault_authority.key,
             *external_pricing_acct.key,
-            false,
+            allow_further_share_creation,
         ),
         accounts,
         &[vault_signing_seeds],
     )?;
 
+    if let Some(profile_info) = compliance_profile {
+        let vault_compliance = vault_compliance.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let compliance_bump = assert_derivation(
+            program_id,
+            vault_compliance,
+            &[VAULT_COMPLIANCE_PREFIX.as_bytes(), vault.key.as_ref()],
+        )?;
+        create_program_account(
+            program_id,
+            payer,
+            vault_compliance,
+            system_program,
+            rent,
+            VAULT_COMPLIANCE_SIZE,
+            &[VAULT_COMPLIANCE_PREFIX.as_bytes(), vault.key.as_ref(), &[compliance_bump]],
+        )?;
+
+        let compliance = VaultCompliance {
+            key: Key::VaultComplianceV1,
+            vault: *vault.key,
+            profile: *profile_info.key,
+            created_ts: Clock::get()?.unix_timestamp,
+            bump: compliance_bump,
+        };
+        compliance.serialize(&mut &mut vault_compliance.data.borrow_mut()[..])?;
+    }
+
     Ok(())
 }
 
@@ -463,9 +805,25 @@ pub fn add_nft_to_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
 
     let _ata_program = next_account_info(accounts_iter)?;
 
+    let portfolio_info = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault.key, vault_pause)?;
+
+    let portfolio_bump = assert_derivation(
+        program_id,
+        portfolio_info,
+        &[PORTFOLIO_PREFIX.as_bytes(), vault.key.as_ref()],
+    )?;
+
     let (_transfer_authority_pda, transfer_bump) = Pubkey::find_program_address(
         &[b"transfer", vault.key.as_ref(), mint.key.as_ref()],
-        &program_id,
+        program_id,
     );
     let transfer_authority_signer_seeds = &[
         b"transfer",
@@ -476,7 +834,7 @@ pub fn add_nft_to_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
 
     let (_store_pda, store_bump) = Pubkey::find_program_address(
         &[b"store", vault.key.as_ref(), mint.key.as_ref()],
-        &program_id,
+        program_id,
     );
     let token_store_signer_seeds = &[
         b"store",
@@ -520,7 +878,7 @@ pub fn add_nft_to_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
             transfer_authority.key,
             payer.key,   // the owner of the nft
             &[], 
-            1 as u64,
+            1,
         )?,
         accounts,
     )?;
@@ -536,7 +894,7 @@ pub fn add_nft_to_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
             *vault_authority.key,
             *payer.key,
             *transfer_authority.key,
-            1 as u64,
+            1,
         ),
         &[
             payer.clone(),
@@ -557,11 +915,46 @@ pub fn add_nft_to_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
         ],
     )?;
 
+    // Create the Portfolio account on the first deposit, then record the new member
+    let mut portfolio = if portfolio_info.data_is_empty() {
+        create_program_account(
+            program_id,
+            payer,
+            portfolio_info,
+            system_program,
+            rent,
+            MAX_PORTFOLIO_SIZE,
+            &[PORTFOLIO_PREFIX.as_bytes(), vault.key.as_ref(), &[portfolio_bump]],
+        )?;
+        Portfolio {
+            key: Key::PortfolioV1,
+            vault: *vault.key,
+            payer: *payer.key,
+            mints: vec![],
+        }
+    } else {
+        assert_owned_by(portfolio_info, program_id)?;
+        Portfolio::from_account_info(portfolio_info)?
+    };
+
+    if portfolio.mints.contains(mint.key) {
+        msg!("rNFT {} is already in this vault", mint.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if portfolio.mints.len() >= MAX_PORTFOLIO_MINTS {
+        msg!("Vault already holds the maximum of {} rNFTs", MAX_PORTFOLIO_MINTS);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    portfolio.mints.push(*mint.key);
+    portfolio.serialize(&mut &mut portfolio_info.data.borrow_mut()[..])?;
+
     Ok(())
 }
 
 pub fn fractionalize(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     number_of_shares: u64,
 ) -> ProgramResult {
@@ -581,10 +974,44 @@ pub fn fractionalize(
 
     let token_vault_program = next_account_info(accounts_iter)?;
 
-    let vault = Vault::from_account_info(vault_info)?;
+    let _token_program = next_account_info(accounts_iter)?;
 
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    // Optional for vaults that are already active, which may predate portfolios
+    let portfolio_info = next_account_info(accounts_iter).ok();
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    let vault = load_vault(vault_info)?;
 
     if vault.state == VaultState::Inactive {
+        let portfolio_info = portfolio_info.ok_or_else(|| {
+            msg!("Portfolio is required to activate the vault");
+            ProgramError::NotEnoughAccountKeys
+        })?;
+        assert_owned_by(portfolio_info, program_id)?;
+        assert_derivation(
+            program_id,
+            portfolio_info,
+            &[PORTFOLIO_PREFIX.as_bytes(), vault_info.key.as_ref()],
+        )?;
+        let portfolio = Portfolio::from_account_info(portfolio_info)?;
+
+        // The shares are backed by every rNFT in the portfolio, so make sure none are missing
+        if portfolio.mints.is_empty() || portfolio.mints.len() != vault.token_type_count as usize {
+            msg!(
+                "Portfolio holds {} rNFTs but the vault has {} safety deposit boxes",
+                portfolio.mints.len(),
+                vault.token_type_count
+            );
+            return Err(ProgramError::InvalidAccountData);
+        }
+
         // Activate the Vault if it is not already, this will mint shares
         invoke(
             &create_activate_vault_instruction(
@@ -617,16 +1044,19 @@ pub fn fractionalize(
     Ok(())
 }
 
+/// Send shares from the Fraction Treasury to the destination, or with a vesting schedule into a vesting
+/// escrow the destination claims them from as they vest
 pub fn send_share(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     number_of_shares: u64,
+    vesting: Option<VestingSchedule>,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
-    let mint = &mut next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
 
-    let payer = &mut next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
 
     let destination = next_account_info(accounts_iter)?;
 
@@ -650,34 +1080,135 @@ pub fn send_share(
 
     let rent_program = next_account_info(accounts_iter)?;
 
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let allowlist = next_account_info(accounts_iter)?;
+
+    let allowlist_entry = next_account_info(accounts_iter)?;
+
+    let vesting_info = next_account_info(accounts_iter)?;
+
+    let vesting_shares = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let vault_compliance = next_account_info(accounts_iter)?;
+
+    let compliance_profile = next_account_info(accounts_iter)?;
+
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+
+    let destination_denied = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault.key, vault_pause)?;
+
+    assert_not_denied(program_id, destination.key, destination_denied)?;
+
+    let profile = load_compliance_profile(program_id, vault.key, vault_compliance, compliance_profile)?
+        .map(|(_, profile)| profile);
+
+    assert_allowlisted(
+        program_id,
+        vault.key,
+        profile.as_ref(),
+        allowlist,
+        allowlist_entry,
+        destination.key,
+        Some(instructions_sysvar),
+    )?;
+
     let (_transfer_authority_pda, transfer_bump) = Pubkey::find_program_address(
         &[b"transfer", vault.key.as_ref(), mint.key.as_ref()],
-        &program_id,
+        program_id,
     );
+    let transfer_signer_seeds: &[&[u8]] =
+        &[b"transfer", vault.key.as_ref(), mint.key.as_ref(), &[transfer_bump]];
 
-    // Check if the destination already has an ATA for this fractional share
-    let token_acct = Account::unpack(&destination_ata.data.borrow());
-    if !token_acct.is_ok() {
-        // Create Associated Token Account for fractional share token
-        invoke(
-            &create_associated_token_account(payer.key, destination.key, fraction_mint.key),
-            &[
-                payer.clone(),
-                destination_ata.clone(),
-                destination.clone(),
-                fraction_mint.clone(),
-                system_program.clone(),
-                token_program.clone(),
-                rent_program.clone(),
-            ],
-        )?;
+    let schedule = match vesting {
+        Some(schedule) => schedule,
+        None => {
+            return withdraw_shares_to(
+                program_id,
+                accounts,
+                payer,
+                destination,
+                destination_ata,
+                transfer_authority,
+                vault,
+                vault_authority,
+                fraction_mint,
+                fraction_treasury,
+                token_vault_program,
+                token_program,
+                system_program,
+                rent_program,
+                holder_registry,
+                profile.as_ref(),
+                transfer_signer_seeds,
+                number_of_shares,
+            )
+        }
+    };
+
+    if !schedule.is_valid() {
+        msg!("Vesting must start before the cliff, which must be no later than the end");
+        return Err(ProgramError::InvalidArgument);
     }
 
-    // Withdraw Share from Fraction Treasury and send to Destination
+    let vesting_bump = assert_derivation(
+        program_id,
+        vesting_info,
+        &[VESTING_PREFIX.as_bytes(), vault.key.as_ref(), destination.key.as_ref()],
+    )?;
+    let shares_bump = assert_derivation(
+        program_id,
+        vesting_shares,
+        &[VESTING_SHARES_PREFIX.as_bytes(), vesting_info.key.as_ref()],
+    )?;
+
+    if !vesting_info.data_is_empty() {
+        msg!("{} must claim their vesting shares of this vault before more can vest", destination.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        vesting_info,
+        system_program,
+        rent,
+        VESTING_SIZE,
+        &[
+            VESTING_PREFIX.as_bytes(),
+            vault.key.as_ref(),
+            destination.key.as_ref(),
+            &[vesting_bump],
+        ],
+    )?;
+
+    // Create the Vesting Escrow that holds the shares until they are claimed
+    create_token_account(
+        payer,
+        vesting_shares,
+        fraction_mint,
+        vesting_info,
+        system_program,
+        rent_program,
+        rent,
+        &[VESTING_SHARES_PREFIX.as_bytes(), vesting_info.key.as_ref(), &[shares_bump]],
+    )?;
+
     invoke_signed(
         &create_withdraw_shares_instruction(
             *token_vault_program.key,
-            *destination_ata.key,
+            *vesting_shares.key,
             *fraction_treasury.key,
             *vault.key,
             *transfer_authority.key,
@@ -685,17 +1216,5162 @@ pub fn send_share(
             number_of_shares,
         ),
         accounts,
-        &[&[
-            b"transfer",
-            vault.key.as_ref(),
-            mint.key.as_ref(),
-            &[transfer_bump],
-        ]],
+        &[transfer_signer_seeds],
     )?;
 
+    let vesting = Vesting {
+        key: Key::VestingV1,
+        vault: *vault.key,
+        recipient: *destination.key,
+        fraction_mint: *fraction_mint.key,
+        payer: *payer.key,
+        schedule,
+        total: number_of_shares,
+        claimed: 0,
+        bump: vesting_bump,
+    };
+    vesting.serialize(&mut &mut vesting_info.data.borrow_mut()[..])?;
+
     Ok(())
 }
 
+/// Send shares to many destinations in one instruction. The accounts are the same as SendShare
+/// without the destination, allowlist entry, vesting, instructions sysvar and denied wallet accounts,
+/// followed by a (destination, destination ATA, allowlist entry, denied wallet) group per entry in `amounts`
+pub fn send_share_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amounts: Vec<u64>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let mint = next_account_info(accounts_iter)?;
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let transfer_authority = next_account_info(accounts_iter)?;
+
+    let vault = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let token_vault_program = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let allowlist = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let vault_compliance = next_account_info(accounts_iter)?;
+
+    let compliance_profile = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault.key, vault_pause)?;
+
+    let profile = load_compliance_profile(program_id, vault.key, vault_compliance, compliance_profile)?
+        .map(|(_, profile)| profile);
+
+    let recipients = accounts_iter.as_slice();
+    if recipients.len() != amounts.len() * 4 {
+        msg!(
+            "Expected {} recipient accounts for {} amounts, got {}",
+            amounts.len() * 4,
+            amounts.len(),
+            recipients.len()
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let (_transfer_authority_pda, transfer_bump) = Pubkey::find_program_address(
+        &[b"transfer", vault.key.as_ref(), mint.key.as_ref()],
+        program_id,
+    );
+
+    for (recipient, number_of_shares) in recipients.chunks(4).zip(amounts) {
+        msg!("Send {} Shares to {}", number_of_shares, recipient[0].key);
+        assert_not_denied(program_id, recipient[0].key, &recipient[3])?;
+        assert_allowlisted(
+            program_id,
+            vault.key,
+            profile.as_ref(),
+            allowlist,
+            &recipient[2],
+            recipient[0].key,
+            None,
+        )?;
+        withdraw_shares_to(
+            program_id,
+            accounts,
+            payer,
+            &recipient[0],
+            &recipient[1],
+            transfer_authority,
+            vault,
+            vault_authority,
+            fraction_mint,
+            fraction_treasury,
+            token_vault_program,
+            token_program,
+            system_program,
+            rent_program,
+            holder_registry,
+            profile.as_ref(),
+            &[b"transfer", vault.key.as_ref(), mint.key.as_ref(), &[transfer_bump]],
+            number_of_shares,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Withdraw shares from the Fraction Treasury to the destination, creating its ATA if needed
+#[allow(clippy::too_many_arguments)]
+fn withdraw_shares_to<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    payer: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    destination_ata: &AccountInfo<'a>,
+    transfer_authority: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    vault_authority: &AccountInfo<'a>,
+    fraction_mint: &AccountInfo<'a>,
+    fraction_treasury: &AccountInfo<'a>,
+    token_vault_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    rent_program: &AccountInfo<'a>,
+    holder_registry: &AccountInfo<'a>,
+    profile: Option<&ComplianceProfile>,
+    transfer_signer_seeds: &[&[u8]],
+    number_of_shares: u64,
+) -> ProgramResult {
+    // Check if the destination already has an ATA for this fractional share
+    let token_acct = Account::unpack(&destination_ata.data.borrow());
+    if token_acct.is_err() {
+        // Create Associated Token Account for fractional share token
+        invoke(
+            &create_associated_token_account(payer.key, destination.key, fraction_mint.key),
+            &[
+                payer.clone(),
+                destination_ata.clone(),
+                destination.clone(),
+                fraction_mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_program.clone(),
+            ],
+        )?;
+    }
+    assert_share_owner(destination_ata, destination.key, fraction_mint.key)?;
+
+    let balance_before = share_balance(destination_ata);
+
+    // Withdraw Share from Fraction Treasury and send to Destination
+    invoke_signed(
+        &create_withdraw_shares_instruction(
+            *token_vault_program.key,
+            *destination_ata.key,
+            *fraction_treasury.key,
+            *vault.key,
+            *transfer_authority.key,
+            *vault_authority.key,
+            number_of_shares,
+        ),
+        accounts,
+        &[transfer_signer_seeds],
+    )?;
+
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        destination_ata,
+        balance_before,
+        true,
+        profile,
+    )
+}
+
+/// Shares in a holder's share account, or 0 if it doesn't exist yet
+fn share_balance(share_account: &AccountInfo) -> u64 {
+    Account::unpack(&share_account.data.borrow())
+        .map(|account| account.amount)
+        .unwrap_or(0)
+}
+
+/// Count a holder share account in or out of the vault's holders now that its balance changed from
+/// `balance_before`. A new holder over the limit is rejected, unless `enforce_limit` is off because the
+/// shares are only coming back to their owner. The limit of the vault's Compliance Profile takes the
+/// place of the registry's own. Vaults without a Holder Registry are not tracked.
+///
+/// Holders are wallets, counted through the one associated token account each wallet has for the fraction
+/// mint. Other share accounts aren't counted, so new shares can't be sent to them.
+fn update_holder_count(
+    program_id: &Pubkey,
+    holder_registry: &AccountInfo,
+    fraction_mint: &AccountInfo,
+    share_account: &AccountInfo,
+    balance_before: u64,
+    enforce_limit: bool,
+    profile: Option<&ComplianceProfile>,
+) -> ProgramResult {
+    assert_derivation(
+        program_id,
+        holder_registry,
+        &[HOLDER_REGISTRY_PREFIX.as_bytes(), fraction_mint.key.as_ref()],
+    )?;
+    if holder_registry.data_is_empty() {
+        return Ok(());
+    }
+
+    assert_owned_by(holder_registry, program_id)?;
+    let mut registry = HolderRegistry::from_account_info(holder_registry)?;
+
+    let max_holders = registry.holder_limit(profile);
+    let balance_after = share_balance(share_account);
+
+    let owner = Account::unpack(&share_account.data.borrow())?.owner;
+    if *share_account.key != get_associated_token_address(&owner, fraction_mint.key) {
+        if enforce_limit && balance_after > balance_before {
+            msg!("Shares of a vault with a holder registry can only go to a wallet's associated token account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(());
+    }
+    if balance_before == 0 && balance_after > 0 {
+        if enforce_limit && max_holders != 0 && registry.holder_count >= max_holders {
+            msg!("Vault already has its maximum of {} holders", max_holders);
+            return Err(ProgramError::InvalidArgument);
+        }
+        registry.holder_count += 1;
+    } else if balance_before > 0 && balance_after == 0 {
+        registry.holder_count = registry.holder_count.saturating_sub(1);
+    } else {
+        return Ok(());
+    }
+
+    registry.serialize(&mut &mut holder_registry.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Mint an rNFT to the payer, create a vault, deposit the rNFT and fractionalize it in one instruction,
+/// so a failure part way through rolls everything back instead of leaving orphaned mints and vaults
+pub fn tokenize(program_id: &Pubkey, accounts: &[AccountInfo], args: TokenizeArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let creator = next_account_info(accounts_iter)?;
+
+    let mint = next_account_info(accounts_iter)?;
+
+    let metadata_account = next_account_info(accounts_iter)?;
+
+    let token_account = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault = next_account_info(accounts_iter)?;
+
+    let vault_mint_authority = next_account_info(accounts_iter)?;
+
+    let external_pricing_acct = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let redeem_treasury = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let transfer_authority = next_account_info(accounts_iter)?;
+
+    let token_store = next_account_info(accounts_iter)?;
+
+    let safety_deposit_box = next_account_info(accounts_iter)?;
+
+    let portfolio = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let metadata_program = next_account_info(accounts_iter)?;
+
+    let token_vault_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let ata_program = next_account_info(accounts_iter)?;
+
+    let native_mint_program = next_account_info(accounts_iter)?;
+
+    let payer_denied = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let payer_role = next_account_info(accounts_iter)?;
+
+    let treasury = next_account_info(accounts_iter)?;
+
+    // Optional Compliance Profile and vault compliance accounts, passed on to CreateVault
+    let compliance_accounts = accounts_iter.as_slice();
+
+    // The rNFT is minted to the payer, who then deposits it into the vault
+    mint_nft(
+        program_id,
+        &[
+            payer.clone(),
+            payer.clone(),
+            creator.clone(),
+            mint.clone(),
+            metadata_account.clone(),
+            token_account.clone(),
+            token_program.clone(),
+            metadata_program.clone(),
+            system_program.clone(),
+            rent_program.clone(),
+            ata_program.clone(),
+            payer_denied.clone(),
+            config_info.clone(),
+            payer_role.clone(),
+            treasury.clone(),
+        ],
+        args.mint.name,
+        args.mint.symbol,
+        args.mint.uri,
+        args.mint.mint_bump,
+        args.mint.mint_seed,
+    )?;
+
+    let mut vault_accounts = vec![
+        payer.clone(),
+        vault_authority.clone(),
+        vault.clone(),
+        vault_mint_authority.clone(),
+        external_pricing_acct.clone(),
+        fraction_mint.clone(),
+        redeem_treasury.clone(),
+        fraction_treasury.clone(),
+        token_vault_program.clone(),
+        token_program.clone(),
+        system_program.clone(),
+        rent_program.clone(),
+        ata_program.clone(),
+        native_mint_program.clone(),
+        config_info.clone(),
+        payer_role.clone(),
+        treasury.clone(),
+    ];
+    vault_accounts.extend_from_slice(compliance_accounts);
+    create_vault(
+        program_id,
+        &vault_accounts,
+        args.vault.vault_seed,
+        args.vault.vault_bump,
+        args.vault.allow_further_share_creation,
+        args.vault.fraction_decimals,
+    )?;
+
+    add_nft_to_vault(
+        program_id,
+        &[
+            mint.clone(),
+            payer.clone(),
+            token_account.clone(),
+            transfer_authority.clone(),
+            vault_authority.clone(),
+            vault.clone(),
+            vault_mint_authority.clone(),
+            token_store.clone(),
+            safety_deposit_box.clone(),
+            token_vault_program.clone(),
+            token_program.clone(),
+            system_program.clone(),
+            rent_program.clone(),
+            ata_program.clone(),
+            portfolio.clone(),
+            vault_pause.clone(),
+            config_info.clone(),
+        ],
+    )?;
+
+    fractionalize(
+        program_id,
+        &[
+            payer.clone(),
+            vault_authority.clone(),
+            vault.clone(),
+            vault_mint_authority.clone(),
+            fraction_mint.clone(),
+            fraction_treasury.clone(),
+            token_vault_program.clone(),
+            token_program.clone(),
+            vault_pause.clone(),
+            config_info.clone(),
+            portfolio.clone(),
+        ],
+        args.number_of_shares,
+    )?;
+
+    Ok(())
+}
+
+/// Burn unsold shares sitting in the fraction treasury of an active vault.
+/// The vault program has no burn instruction, so the shares are withdrawn into a
+/// Tokrizer owned burn account and burned from there.
+pub fn burn_treasury_shares(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    number_of_shares: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let vault_mint_authority = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let burn_account = next_account_info(accounts_iter)?;
+
+    let token_vault_program = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let _system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to burn treasury shares");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if vault.state != VaultState::Active {
+        msg!("Treasury shares can only be burned from an active vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if vault.fraction_mint != *fraction_mint.key || vault.fraction_treasury != *fraction_treasury.key {
+        msg!("Fraction mint or treasury does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let burn_bump = assert_derivation(
+        program_id,
+        burn_account,
+        &[b"burn", vault_info.key.as_ref()],
+    )?;
+    let burn_signer_seeds = &[b"burn", vault_info.key.as_ref(), &[burn_bump]];
+
+    // Create the Burn account the first time, it owns itself so this program can sign the burn
+    if burn_account.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                burn_account.key,
+                rent.minimum_balance(Account::LEN),
+                Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            accounts,
+            &[burn_signer_seeds],
+        )?;
+
+        invoke(
+            &initialize_account(
+                &spl_token::id(),
+                burn_account.key,
+                fraction_mint.key,
+                burn_account.key,
+            )?,
+            accounts,
+        )?;
+    }
+
+    // Withdraw the shares from the Fraction Treasury into the Burn account
+    invoke(
+        &create_withdraw_shares_instruction(
+            *token_vault_program.key,
+            *burn_account.key,
+            *fraction_treasury.key,
+            *vault_info.key,
+            *vault_mint_authority.key,
+            *vault_authority.key,
+            number_of_shares,
+        ),
+        accounts,
+    )?;
+
+    // Burn them, shrinking the outstanding supply of the Fractional Mint
+    invoke_signed(
+        &burn(
+            &spl_token::id(),
+            burn_account.key,
+            fraction_mint.key,
+            burn_account.key,
+            &[],
+            number_of_shares,
+        )?,
+        accounts,
+        &[burn_signer_seeds],
+    )?;
+
+    Ok(())
+}
+
+/// Reclaim the rent of the Tokrizer owned accounts of a vault once the rNFTs have been combined and withdrawn.
+/// The vault, safety deposit boxes, token stores, treasuries and external pricing account belong to the
+/// vault program (or its PDA), which has no instruction to close them, so their rent stays locked.
+pub fn close_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let rent_receiver = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let portfolio_info = next_account_info(accounts_iter)?;
+
+    let burn_account = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to close the vault");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // A vault is only deactivated once it has been combined and every safety deposit box emptied
+    if vault.state != VaultState::Deactivated || vault.token_type_count != 0 {
+        msg!("Vault must be combined and all rNFTs withdrawn before it can be closed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    assert_owned_by(portfolio_info, program_id)?;
+    assert_derivation(
+        program_id,
+        portfolio_info,
+        &[PORTFOLIO_PREFIX.as_bytes(), vault_info.key.as_ref()],
+    )?;
+    let portfolio = Portfolio::from_account_info(portfolio_info)?;
+
+    if portfolio.payer != *rent_receiver.key {
+        msg!("Rent can only be returned to the original payer {}", portfolio.payer);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let burn_bump = assert_derivation(
+        program_id,
+        burn_account,
+        &[b"burn", vault_info.key.as_ref()],
+    )?;
+
+    // Close the Burn account if BurnTreasuryShares was ever used on this vault
+    if !burn_account.data_is_empty() {
+        let burn_token_account = Account::unpack(&burn_account.data.borrow())?;
+        if burn_token_account.amount != 0 {
+            msg!("Burn account still holds {} shares", burn_token_account.amount);
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        invoke_signed(
+            &close_account(
+                &spl_token::id(),
+                burn_account.key,
+                rent_receiver.key,
+                burn_account.key,
+                &[],
+            )?,
+            accounts,
+            &[&[b"burn", vault_info.key.as_ref(), &[burn_bump]]],
+        )?;
+    }
+
+    close_program_account(portfolio_info, rent_receiver)?;
+
+    Ok(())
+}
+
+/// Open a fixed price primary sale of an active vault's shares. The shares for sale are withdrawn
+/// from the fraction treasury into an escrow owned by the offering so buyers don't need the vault authority.
+pub fn open_offering(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: OpenOfferingArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let vault_mint_authority = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let offering_info = next_account_info(accounts_iter)?;
+
+    let offering_shares = next_account_info(accounts_iter)?;
+
+    let payment_mint = next_account_info(accounts_iter)?;
+
+    let proceeds = next_account_info(accounts_iter)?;
+
+    let token_vault_program = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let offering_nonce_info = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to open an offering");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if vault.fraction_mint != *fraction_mint.key || vault.fraction_treasury != *fraction_treasury.key {
+        msg!("Fraction mint or treasury does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.number_of_shares == 0 || args.end_ts <= args.start_ts {
+        msg!("Offering needs shares to sell and must end after it starts");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.min_shares > args.number_of_shares {
+        msg!("Minimum raise of {} shares is more than the {} offered", args.min_shares, args.number_of_shares);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let offering_bump = assert_derivation(
+        program_id,
+        offering_info,
+        &[OFFERING_PREFIX.as_bytes(), vault_info.key.as_ref()],
+    )?;
+    let offering_signer_seeds = &[OFFERING_PREFIX.as_bytes(), vault_info.key.as_ref(), &[offering_bump]];
+
+    let shares_bump = assert_derivation(
+        program_id,
+        offering_shares,
+        &[OFFERING_SHARES_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+
+    let proceeds_bump = assert_derivation(
+        program_id,
+        proceeds,
+        &[PROCEEDS_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+
+    if !offering_info.data_is_empty() {
+        msg!("Vault already has an open offering");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = &Rent::from_account_info(rent_program)?;
+
+    // Number this offering, so Purchase records left from an earlier one on the vault are recognised
+    let nonce_bump = assert_derivation(
+        program_id,
+        offering_nonce_info,
+        &[OFFERING_NONCE_PREFIX.as_bytes(), vault_info.key.as_ref()],
+    )?;
+    let mut offering_nonce = if offering_nonce_info.data_is_empty() {
+        create_program_account(
+            program_id,
+            payer,
+            offering_nonce_info,
+            system_program,
+            rent,
+            OFFERING_NONCE_SIZE,
+            &[OFFERING_NONCE_PREFIX.as_bytes(), vault_info.key.as_ref(), &[nonce_bump]],
+        )?;
+        OfferingNonce {
+            key: Key::OfferingNonceV1,
+            vault: *vault_info.key,
+            nonce: 0,
+        }
+    } else {
+        assert_owned_by(offering_nonce_info, program_id)?;
+        OfferingNonce::from_account_info(offering_nonce_info)?
+    };
+    offering_nonce.nonce = offering_nonce.nonce.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+    offering_nonce.serialize(&mut &mut offering_nonce_info.data.borrow_mut()[..])?;
+
+    // Create Offering account
+    create_program_account(
+        program_id,
+        payer,
+        offering_info,
+        system_program,
+        rent,
+        OFFERING_SIZE,
+        offering_signer_seeds,
+    )?;
+
+    // Create the share escrow and the proceeds account, both owned by the Offering
+    create_token_account(
+        payer,
+        offering_shares,
+        fraction_mint,
+        offering_info,
+        system_program,
+        rent_program,
+        rent,
+        &[OFFERING_SHARES_PREFIX.as_bytes(), offering_info.key.as_ref(), &[shares_bump]],
+    )?;
+
+    create_token_account(
+        payer,
+        proceeds,
+        payment_mint,
+        offering_info,
+        system_program,
+        rent_program,
+        rent,
+        &[PROCEEDS_PREFIX.as_bytes(), offering_info.key.as_ref(), &[proceeds_bump]],
+    )?;
+
+    // Move the shares for sale out of the Fraction Treasury
+    invoke(
+        &create_withdraw_shares_instruction(
+            *token_vault_program.key,
+            *offering_shares.key,
+            *fraction_treasury.key,
+            *vault_info.key,
+            *vault_mint_authority.key,
+            *vault_authority.key,
+            args.number_of_shares,
+        ),
+        accounts,
+    )?;
+
+    let offering = Offering {
+        key: Key::OfferingV1,
+        vault: *vault_info.key,
+        authority: *vault_authority.key,
+        payer: *payer.key,
+        fraction_mint: *fraction_mint.key,
+        payment_mint: *payment_mint.key,
+        price_per_share: args.price_per_share,
+        start_ts: args.start_ts,
+        end_ts: args.end_ts,
+        max_shares_per_wallet: args.max_shares_per_wallet,
+        shares_offered: args.number_of_shares,
+        shares_sold: 0,
+        bump: offering_bump,
+        min_shares: args.min_shares,
+        state: OfferingState::Open,
+        open_purchases: 0,
+        nonce: offering_nonce.nonce,
+    };
+    offering.serialize(&mut &mut offering_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Buy shares from an open offering, paying into the proceeds account and receiving the shares in the same instruction.
+/// For an offering with a minimum raise the payment and shares stay in escrow until it is finalized.
+pub fn buy_shares(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    number_of_shares: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let buyer = next_account_info(accounts_iter)?;
+
+    let buyer_payment_account = next_account_info(accounts_iter)?;
+
+    let buyer_share_account = next_account_info(accounts_iter)?;
+
+    let offering_info = next_account_info(accounts_iter)?;
+
+    let purchase_info = next_account_info(accounts_iter)?;
+
+    let offering_shares = next_account_info(accounts_iter)?;
+
+    let proceeds = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let allowlist = next_account_info(accounts_iter)?;
+
+    let allowlist_entry = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let vault_compliance = next_account_info(accounts_iter)?;
+
+    let compliance_profile = next_account_info(accounts_iter)?;
+
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+
+    let buyer_denied = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !buyer.is_signer {
+        msg!("Buyer must sign to buy shares");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_not_denied(program_id, buyer.key, buyer_denied)?;
+
+    assert_owned_by(offering_info, program_id)?;
+    let mut offering = Offering::from_account_info(offering_info)?;
+
+    assert_vault_not_paused(program_id, &offering.vault, vault_pause)?;
+
+    let profile = load_compliance_profile(program_id, &offering.vault, vault_compliance, compliance_profile)?
+        .map(|(_, profile)| profile);
+
+    assert_allowlisted(
+        program_id,
+        &offering.vault,
+        profile.as_ref(),
+        allowlist,
+        allowlist_entry,
+        buyer.key,
+        Some(instructions_sysvar),
+    )?;
+
+    assert_derivation(
+        program_id,
+        offering_shares,
+        &[OFFERING_SHARES_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+    assert_derivation(
+        program_id,
+        proceeds,
+        &[PROCEEDS_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+
+    if offering.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this offering");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if offering.state != OfferingState::Open || now < offering.start_ts || now >= offering.end_ts {
+        msg!("Offering is only open from {} to {}", offering.start_ts, offering.end_ts);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let remaining = offering.shares_offered - offering.shares_sold;
+    if number_of_shares == 0 || number_of_shares > remaining {
+        msg!("Only {} shares are left in this offering", remaining);
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    // Load or create the buyer's Purchase record to enforce the per wallet cap
+    let purchase_bump = assert_derivation(
+        program_id,
+        purchase_info,
+        &[PURCHASE_PREFIX.as_bytes(), offering_info.key.as_ref(), buyer.key.as_ref()],
+    )?;
+    let rent = &Rent::from_account_info(rent_program)?;
+    let mut purchase = if purchase_info.data_is_empty() {
+        create_program_account(
+            program_id,
+            buyer,
+            purchase_info,
+            system_program,
+            rent,
+            PURCHASE_SIZE,
+            &[
+                PURCHASE_PREFIX.as_bytes(),
+                offering_info.key.as_ref(),
+                buyer.key.as_ref(),
+                &[purchase_bump],
+            ],
+        )?;
+        Purchase {
+            key: Key::PurchaseV1,
+            offering: *offering_info.key,
+            buyer: *buyer.key,
+            offering_nonce: offering.nonce,
+            shares_purchased: 0,
+            amount_paid: 0,
+        }
+    } else {
+        assert_owned_by(purchase_info, program_id)?;
+        Purchase::from_account_info(purchase_info)?
+    };
+
+    // A record left over from an earlier offering on the same vault starts again from zero
+    if purchase.offering_nonce != offering.nonce {
+        purchase.offering_nonce = offering.nonce;
+        purchase.shares_purchased = 0;
+        purchase.amount_paid = 0;
+    }
+
+    let shares_purchased = purchase
+        .shares_purchased
+        .checked_add(number_of_shares)
+        .ok_or(ProgramError::InvalidArgument)?;
+    if offering.max_shares_per_wallet != 0 && shares_purchased > offering.max_shares_per_wallet {
+        msg!(
+            "Wallet may buy at most {} shares, already bought {}",
+            offering.max_shares_per_wallet,
+            purchase.shares_purchased
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let cost = offering
+        .price_per_share
+        .checked_mul(number_of_shares)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    // Pay for the shares
+    invoke(
+        &transfer(
+            &spl_token::id(),
+            buyer_payment_account.key,
+            proceeds.key,
+            buyer.key,
+            &[],
+            cost,
+        )?,
+        accounts,
+    )?;
+
+    if offering.min_shares > 0 {
+        // Escrowed: the shares stay reserved in the share escrow until the offering is finalized
+        if purchase.shares_purchased == 0 {
+            offering.open_purchases += 1;
+        }
+        purchase.amount_paid = purchase
+            .amount_paid
+            .checked_add(cost)
+            .ok_or(ProgramError::InvalidArgument)?;
+    } else {
+        // Create the buyer's Associated Token Account for the fractional share if needed
+        if Account::unpack(&buyer_share_account.data.borrow()).is_err() {
+            invoke(
+                &create_associated_token_account(buyer.key, buyer.key, fraction_mint.key),
+                &[
+                    buyer.clone(),
+                    buyer_share_account.clone(),
+                    buyer.clone(),
+                    fraction_mint.clone(),
+                    system_program.clone(),
+                    token_program.clone(),
+                    rent_program.clone(),
+                ],
+            )?;
+        }
+        assert_share_owner(buyer_share_account, buyer.key, fraction_mint.key)?;
+
+        // Release the shares from the escrow
+        let balance_before = share_balance(buyer_share_account);
+        invoke_signed(
+            &transfer(
+                &spl_token::id(),
+                offering_shares.key,
+                buyer_share_account.key,
+                offering_info.key,
+                &[],
+                number_of_shares,
+            )?,
+            accounts,
+            &[&[OFFERING_PREFIX.as_bytes(), offering.vault.as_ref(), &[offering.bump]]],
+        )?;
+        update_holder_count(
+            program_id,
+            holder_registry,
+            fraction_mint,
+            buyer_share_account,
+            balance_before,
+            true,
+            profile.as_ref(),
+        )?;
+    }
+
+    purchase.shares_purchased = shares_purchased;
+    purchase.serialize(&mut &mut purchase_info.data.borrow_mut()[..])?;
+
+    offering.shares_sold += number_of_shares;
+    offering.serialize(&mut &mut offering_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Close an offering: the proceeds go to the vault authority, unsold shares go back to the
+/// fraction treasury and the offering accounts' rent goes back to whoever opened it
+pub fn close_offering(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let rent_receiver = next_account_info(accounts_iter)?;
+
+    let offering_info = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let offering_shares = next_account_info(accounts_iter)?;
+
+    let proceeds = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let proceeds_destination = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let fee_account = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to close an offering");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(offering_info, program_id)?;
+    let offering = Offering::from_account_info(offering_info)?;
+
+    if offering.authority != *vault_authority.key {
+        msg!("Signer is not the offering authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if offering.payer != *rent_receiver.key {
+        msg!("Rent can only be returned to the original payer {}", offering.payer);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if offering.min_shares > 0 && (offering.state == OfferingState::Open || offering.open_purchases > 0) {
+        msg!(
+            "Escrowed offering must be finalized and all {} purchases settled before closing",
+            offering.open_purchases
+        );
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    assert_derivation(
+        program_id,
+        offering_shares,
+        &[OFFERING_SHARES_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+    assert_derivation(
+        program_id,
+        proceeds,
+        &[PROCEEDS_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+
+    if offering.vault != *vault_info.key {
+        msg!("Vault does not belong to this offering");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.fraction_treasury != *fraction_treasury.key {
+        msg!("Fraction treasury does not belong to this offering's vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let offering_signer_seeds: &[&[u8]] = &[OFFERING_PREFIX.as_bytes(), offering.vault.as_ref(), &[offering.bump]];
+
+    // Return unsold shares to the Fraction Treasury
+    let unsold = Account::unpack(&offering_shares.data.borrow())?.amount;
+    if unsold > 0 {
+        invoke_signed(
+            &transfer(
+                &spl_token::id(),
+                offering_shares.key,
+                fraction_treasury.key,
+                offering_info.key,
+                &[],
+                unsold,
+            )?,
+            accounts,
+            &[offering_signer_seeds],
+        )?;
+    }
+
+    // Send the proceeds to the vault authority
+    pay_out_proceeds(
+        program_id,
+        accounts,
+        config_info,
+        fee_account,
+        proceeds,
+        proceeds_destination,
+        offering_info,
+        &offering,
+    )?;
+
+    for token_account in [offering_shares, proceeds] {
+        invoke_signed(
+            &close_account(
+                &spl_token::id(),
+                token_account.key,
+                rent_receiver.key,
+                offering_info.key,
+                &[],
+            )?,
+            accounts,
+            &[offering_signer_seeds],
+        )?;
+    }
+
+    close_program_account(offering_info, rent_receiver)?;
+
+    Ok(())
+}
+
+/// Settle an escrowed offering once it has ended, or sold out early. If the minimum raise was met the
+/// proceeds go to the vault authority and buyers can claim their shares, otherwise buyers can get refunds.
+pub fn finalize_offering(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let offering_info = next_account_info(accounts_iter)?;
+
+    let proceeds = next_account_info(accounts_iter)?;
+
+    let proceeds_destination = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let fee_account = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    assert_owned_by(offering_info, program_id)?;
+    let mut offering = Offering::from_account_info(offering_info)?;
+
+    assert_vault_not_paused(program_id, &offering.vault, vault_pause)?;
+
+    assert_derivation(
+        program_id,
+        proceeds,
+        &[PROCEEDS_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+
+    if offering.min_shares == 0 || offering.state != OfferingState::Open {
+        msg!("Offering is not an open escrowed offering");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if now < offering.end_ts && offering.shares_sold < offering.shares_offered {
+        msg!("Offering runs until {}", offering.end_ts);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if offering.shares_sold < offering.min_shares {
+        msg!(
+            "Offering failed, sold {} of the minimum {} shares",
+            offering.shares_sold,
+            offering.min_shares
+        );
+        offering.state = OfferingState::Failed;
+        offering.serialize(&mut &mut offering_info.data.borrow_mut()[..])?;
+        return Ok(());
+    }
+
+    // Anyone can finalize, so make sure the proceeds only go to the vault authority
+    let destination = Account::unpack(&proceeds_destination.data.borrow())?;
+    if destination.owner != offering.authority || destination.mint != offering.payment_mint {
+        msg!("Proceeds must go to a {} account of the vault authority", offering.payment_mint);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    pay_out_proceeds(
+        program_id,
+        accounts,
+        config_info,
+        fee_account,
+        proceeds,
+        proceeds_destination,
+        offering_info,
+        &offering,
+    )?;
+
+    offering.state = OfferingState::Succeeded;
+    offering.serialize(&mut &mut offering_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Deliver a buyer's reserved shares from a successful escrowed offering. Anyone can crank this for a buyer.
+pub fn claim_purchase(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let buyer = next_account_info(accounts_iter)?;
+
+    let buyer_share_account = next_account_info(accounts_iter)?;
+
+    let offering_info = next_account_info(accounts_iter)?;
+
+    let purchase_info = next_account_info(accounts_iter)?;
+
+    let offering_shares = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let allowlist = next_account_info(accounts_iter)?;
+
+    let allowlist_entry = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let vault_compliance = next_account_info(accounts_iter)?;
+
+    let compliance_profile = next_account_info(accounts_iter)?;
+
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+
+    let buyer_denied = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_not_denied(program_id, buyer.key, buyer_denied)?;
+
+    assert_owned_by(offering_info, program_id)?;
+    let mut offering = Offering::from_account_info(offering_info)?;
+
+    assert_vault_not_paused(program_id, &offering.vault, vault_pause)?;
+
+    if offering.state != OfferingState::Succeeded {
+        msg!("Shares can only be claimed from a successful offering");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let profile = load_compliance_profile(program_id, &offering.vault, vault_compliance, compliance_profile)?
+        .map(|(_, profile)| profile);
+
+    // The buyer may have lost their verification while the offering was escrowed
+    assert_allowlisted(
+        program_id,
+        &offering.vault,
+        profile.as_ref(),
+        allowlist,
+        allowlist_entry,
+        buyer.key,
+        Some(instructions_sysvar),
+    )?;
+
+    let purchase = load_escrowed_purchase(program_id, offering_info, &offering, purchase_info, buyer)?;
+
+    assert_derivation(
+        program_id,
+        offering_shares,
+        &[OFFERING_SHARES_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+
+    if offering.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this offering");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Anyone can claim, so make sure the shares only go to the buyer
+    if *buyer_share_account.key != get_associated_token_address(&purchase.buyer, fraction_mint.key) {
+        msg!("Shares must go to the buyer's associated token account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if Account::unpack(&buyer_share_account.data.borrow()).is_err() {
+        invoke(
+            &create_associated_token_account(payer.key, buyer.key, fraction_mint.key),
+            &[
+                payer.clone(),
+                buyer_share_account.clone(),
+                buyer.clone(),
+                fraction_mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_program.clone(),
+            ],
+        )?;
+    }
+
+    let balance_before = share_balance(buyer_share_account);
+    invoke_signed(
+        &transfer(
+            &spl_token::id(),
+            offering_shares.key,
+            buyer_share_account.key,
+            offering_info.key,
+            &[],
+            purchase.shares_purchased,
+        )?,
+        accounts,
+        &[&[OFFERING_PREFIX.as_bytes(), offering.vault.as_ref(), &[offering.bump]]],
+    )?;
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        buyer_share_account,
+        balance_before,
+        true,
+        profile.as_ref(),
+    )?;
+
+    close_program_account(purchase_info, buyer)?;
+
+    offering.open_purchases -= 1;
+    offering.serialize(&mut &mut offering_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Refund a buyer of a failed escrowed offering and return their reserved shares to the fraction treasury
+pub fn refund_purchase(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let buyer = next_account_info(accounts_iter)?;
+
+    let buyer_payment_account = next_account_info(accounts_iter)?;
+
+    let offering_info = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let purchase_info = next_account_info(accounts_iter)?;
+
+    let offering_shares = next_account_info(accounts_iter)?;
+
+    let proceeds = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let buyer_denied = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter).ok();
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    assert_owned_by(offering_info, program_id)?;
+    let mut offering = Offering::from_account_info(offering_info)?;
+
+    let purchase = load_escrowed_purchase(program_id, offering_info, &offering, purchase_info, buyer)?;
+
+    assert_derivation(
+        program_id,
+        offering_shares,
+        &[OFFERING_SHARES_PREFIX.as_bytes(), offering_info.key.as_ref()],
+    )?;
+
+    // Refunds only go to the buyer's own account, so a denied buyer gets theirs too. A successful offering already
+    // paid its proceeds to the vault authority, which refunds a buyer denied before claiming from its own account.
+    let refund_authority = match offering.state {
+        OfferingState::Failed => {
+            assert_derivation(
+                program_id,
+                proceeds,
+                &[PROCEEDS_PREFIX.as_bytes(), offering_info.key.as_ref()],
+            )?;
+            offering_info
+        }
+        OfferingState::Succeeded => {
+            assert_derivation(program_id, buyer_denied, &[DENIED_WALLET_PREFIX.as_bytes(), buyer.key.as_ref()])?;
+            if buyer_denied.data_is_empty() {
+                msg!("A successful offering only refunds buyers on the deny list, who can't claim their shares");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let vault_authority = vault_authority.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if !vault_authority.is_signer {
+                msg!("Vault authority must sign to refund a successful offering");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if offering.authority != *vault_authority.key {
+                msg!("Signer is not the offering authority");
+                return Err(ProgramError::IllegalOwner);
+            }
+            vault_authority
+        }
+        OfferingState::Open => {
+            msg!("Offering has not been finalized");
+            return Err(ProgramError::InvalidAccountData);
+        }
+    };
+
+    if offering.vault != *vault_info.key {
+        msg!("Vault does not belong to this offering");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.fraction_treasury != *fraction_treasury.key {
+        msg!("Fraction treasury does not belong to this offering's vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let refund_account = Account::unpack(&buyer_payment_account.data.borrow())?;
+    if refund_account.owner != purchase.buyer || refund_account.mint != offering.payment_mint {
+        msg!("Refund must go to a {} account of the buyer", offering.payment_mint);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let offering_signer_seeds: &[&[u8]] = &[OFFERING_PREFIX.as_bytes(), offering.vault.as_ref(), &[offering.bump]];
+
+    // Return the payment to the buyer
+    invoke_signed(
+        &transfer(
+            &spl_token::id(),
+            proceeds.key,
+            buyer_payment_account.key,
+            refund_authority.key,
+            &[],
+            purchase.amount_paid,
+        )?,
+        accounts,
+        &[offering_signer_seeds],
+    )?;
+
+    // Return the reserved shares to the Fraction Treasury
+    invoke_signed(
+        &transfer(
+            &spl_token::id(),
+            offering_shares.key,
+            fraction_treasury.key,
+            offering_info.key,
+            &[],
+            purchase.shares_purchased,
+        )?,
+        accounts,
+        &[offering_signer_seeds],
+    )?;
+
+    close_program_account(purchase_info, buyer)?;
+
+    offering.open_purchases -= 1;
+    offering.shares_offered -= purchase.shares_purchased;
+    offering.shares_sold -= purchase.shares_purchased;
+    offering.serialize(&mut &mut offering_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Close a buyer's Purchase record once its offering is over, returning the rent to the buyer. Anyone can
+/// crank this for a buyer.
+pub fn close_purchase(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let buyer = next_account_info(accounts_iter)?;
+
+    let purchase_info = next_account_info(accounts_iter)?;
+
+    let offering_info = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_owned_by(purchase_info, program_id)?;
+    assert_derivation(
+        program_id,
+        purchase_info,
+        &[PURCHASE_PREFIX.as_bytes(), offering_info.key.as_ref(), buyer.key.as_ref()],
+    )?;
+    let purchase = Purchase::from_account_info(purchase_info)?;
+
+    // A closed offering settled every escrowed purchase, and a newer one doesn't use this record
+    if !offering_info.data_is_empty() {
+        assert_owned_by(offering_info, program_id)?;
+        let offering = Offering::from_account_info(offering_info)?;
+        if offering.nonce == purchase.offering_nonce {
+            msg!("Purchase is still in use by the open offering");
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    close_program_account(purchase_info, buyer)?;
+
+    Ok(())
+}
+
+fn load_escrowed_purchase(
+    program_id: &Pubkey,
+    offering_info: &AccountInfo,
+    offering: &Offering,
+    purchase_info: &AccountInfo,
+    buyer: &AccountInfo,
+) -> Result<Purchase, ProgramError> {
+    assert_owned_by(purchase_info, program_id)?;
+    assert_derivation(
+        program_id,
+        purchase_info,
+        &[PURCHASE_PREFIX.as_bytes(), offering_info.key.as_ref(), buyer.key.as_ref()],
+    )?;
+    let purchase = Purchase::from_account_info(purchase_info)?;
+
+    if purchase.offering_nonce != offering.nonce || purchase.shares_purchased == 0 {
+        msg!("Purchase has nothing escrowed in this offering");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(purchase)
+}
+
+/// Set up rental income distribution for a vault, paid in `payment_mint` and deposited by `manager`
+pub fn init_distribution(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let manager = next_account_info(accounts_iter)?;
+
+    let distribution_info = next_account_info(accounts_iter)?;
+
+    let income = next_account_info(accounts_iter)?;
+
+    let staked = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let payment_mint = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let manager_role = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    assert_role(program_id, config_info, manager_role, manager.key, Role::PropertyManager)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to set up a distribution");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if vault.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let distribution_bump = assert_derivation(
+        program_id,
+        distribution_info,
+        &[DISTRIBUTION_PREFIX.as_bytes(), vault_info.key.as_ref()],
+    )?;
+    let income_bump = assert_derivation(
+        program_id,
+        income,
+        &[INCOME_PREFIX.as_bytes(), distribution_info.key.as_ref()],
+    )?;
+    let staked_bump = assert_derivation(
+        program_id,
+        staked,
+        &[STAKED_PREFIX.as_bytes(), distribution_info.key.as_ref()],
+    )?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+
+    // Create Distribution account
+    create_program_account(
+        program_id,
+        payer,
+        distribution_info,
+        system_program,
+        rent,
+        DISTRIBUTION_SIZE,
+        &[DISTRIBUTION_PREFIX.as_bytes(), vault_info.key.as_ref(), &[distribution_bump]],
+    )?;
+
+    // Create the income and staked share pools, both owned by the Distribution
+    create_token_account(
+        payer,
+        income,
+        payment_mint,
+        distribution_info,
+        system_program,
+        rent_program,
+        rent,
+        &[INCOME_PREFIX.as_bytes(), distribution_info.key.as_ref(), &[income_bump]],
+    )?;
+
+    create_token_account(
+        payer,
+        staked,
+        fraction_mint,
+        distribution_info,
+        system_program,
+        rent_program,
+        rent,
+        &[STAKED_PREFIX.as_bytes(), distribution_info.key.as_ref(), &[staked_bump]],
+    )?;
+
+    let distribution = Distribution {
+        key: Key::DistributionV1,
+        vault: *vault_info.key,
+        fraction_mint: *fraction_mint.key,
+        payment_mint: *payment_mint.key,
+        manager: *manager.key,
+        total_staked: 0,
+        income_per_share: 0,
+        bump: distribution_bump,
+    };
+    distribution.serialize(&mut &mut distribution_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// The property manager deposits income, which is credited pro-rata to every staked share
+pub fn deposit_income(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let manager = next_account_info(accounts_iter)?;
+
+    let manager_payment_account = next_account_info(accounts_iter)?;
+
+    let distribution_info = next_account_info(accounts_iter)?;
+
+    let income = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let manager_role = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, manager_role, manager.key, Role::PropertyManager)?;
+
+    if !manager.is_signer {
+        msg!("Property manager must sign to deposit income");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(distribution_info, program_id)?;
+    let mut distribution = Distribution::from_account_info(distribution_info)?;
+
+    assert_vault_not_paused(program_id, &distribution.vault, vault_pause)?;
+
+    if distribution.manager != *manager.key {
+        msg!("Signer is not the property manager of this distribution");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    assert_derivation(
+        program_id,
+        income,
+        &[INCOME_PREFIX.as_bytes(), distribution_info.key.as_ref()],
+    )?;
+
+    distribution.credit_income(amount)?;
+
+    invoke(
+        &transfer(
+            &spl_token::id(),
+            manager_payment_account.key,
+            income.key,
+            manager.key,
+            &[],
+            amount,
+        )?,
+        accounts,
+    )?;
+
+    distribution.serialize(&mut &mut distribution_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Pay a holder the income owed on their staked shares, then stake or unstake shares.
+/// Settling before every balance change is what stops income being claimed twice for the same share.
+pub fn update_stake(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    stake_amount: u64,
+    unstake_amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let holder = next_account_info(accounts_iter)?;
+
+    let holder_share_account = next_account_info(accounts_iter)?;
+
+    let holder_payment_account = next_account_info(accounts_iter)?;
+
+    let distribution_info = next_account_info(accounts_iter)?;
+
+    let stake_info = next_account_info(accounts_iter)?;
+
+    let staked = next_account_info(accounts_iter)?;
+
+    let income = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let holder_denied = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !holder.is_signer {
+        msg!("Holder must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(distribution_info, program_id)?;
+    let mut distribution = Distribution::from_account_info(distribution_info)?;
+
+    assert_vault_not_paused(program_id, &distribution.vault, vault_pause)?;
+
+    if distribution.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this distribution");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_derivation(
+        program_id,
+        staked,
+        &[STAKED_PREFIX.as_bytes(), distribution_info.key.as_ref()],
+    )?;
+    assert_derivation(
+        program_id,
+        income,
+        &[INCOME_PREFIX.as_bytes(), distribution_info.key.as_ref()],
+    )?;
+    let stake_bump = assert_derivation(
+        program_id,
+        stake_info,
+        &[STAKE_PREFIX.as_bytes(), distribution_info.key.as_ref(), holder.key.as_ref()],
+    )?;
+
+    let mut stake = if stake_info.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        create_program_account(
+            program_id,
+            holder,
+            stake_info,
+            system_program,
+            rent,
+            HOLDER_STAKE_SIZE,
+            &[
+                STAKE_PREFIX.as_bytes(),
+                distribution_info.key.as_ref(),
+                holder.key.as_ref(),
+                &[stake_bump],
+            ],
+        )?;
+        HolderStake {
+            key: Key::HolderStakeV1,
+            distribution: *distribution_info.key,
+            holder: *holder.key,
+            amount: 0,
+            income_debt: 0,
+        }
+    } else {
+        assert_owned_by(stake_info, program_id)?;
+        HolderStake::from_account_info(stake_info)?
+    };
+
+    let distribution_signer_seeds: &[&[u8]] = &[
+        DISTRIBUTION_PREFIX.as_bytes(),
+        distribution.vault.as_ref(),
+        &[distribution.bump],
+    ];
+
+    // Pay out everything deposited since the holder was last settled
+    let pending = stake.pending_income(distribution.income_per_share)?;
+    let owed = u64::try_from(pending / INCOME_PER_SHARE_PRECISION).map_err(|_| ProgramError::InvalidArgument)?;
+    if owed > 0 {
+        invoke_signed(
+            &transfer(
+                &spl_token::id(),
+                income.key,
+                holder_payment_account.key,
+                distribution_info.key,
+                &[],
+                owed,
+            )?,
+            accounts,
+            &[distribution_signer_seeds],
+        )?;
+    }
+
+    if stake_amount > 0 {
+        let balance_before = share_balance(holder_share_account);
+        invoke(
+            &transfer(
+                &spl_token::id(),
+                holder_share_account.key,
+                staked.key,
+                holder.key,
+                &[],
+                stake_amount,
+            )?,
+            accounts,
+        )?;
+        update_holder_count(
+            program_id,
+            holder_registry,
+            fraction_mint,
+            holder_share_account,
+            balance_before,
+            false,
+            None,
+        )?;
+    }
+
+    if unstake_amount > stake.amount {
+        msg!("Only {} shares are staked", stake.amount);
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let staked_amount = stake
+        .amount
+        .checked_add(stake_amount)
+        .and_then(|amount| amount.checked_sub(unstake_amount))
+        .ok_or(ProgramError::InvalidArgument)?;
+    let total_staked = distribution
+        .total_staked
+        .checked_add(stake_amount)
+        .and_then(|total| total.checked_sub(unstake_amount))
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    if unstake_amount > 0 {
+        // Staked shares leave the program's custody here, so they only go back to an account the holder owns,
+        // and not at all while the holder is denied
+        assert_not_denied(program_id, holder.key, holder_denied)?;
+        assert_share_owner(holder_share_account, holder.key, fraction_mint.key)?;
+
+        let balance_before = share_balance(holder_share_account);
+        invoke_signed(
+            &transfer(
+                &spl_token::id(),
+                staked.key,
+                holder_share_account.key,
+                distribution_info.key,
+                &[],
+                unstake_amount,
+            )?,
+            accounts,
+            &[distribution_signer_seeds],
+        )?;
+        update_holder_count(
+            program_id,
+            holder_registry,
+            fraction_mint,
+            holder_share_account,
+            balance_before,
+            false,
+            None,
+        )?;
+    }
+
+    stake.restake(staked_amount, distribution.income_per_share, pending)?;
+    stake.serialize(&mut &mut stake_info.data.borrow_mut()[..])?;
+
+    distribution.total_staked = total_staked;
+    distribution.serialize(&mut &mut distribution_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Record the Merkle root of every (holder, balance) of a vault's shares at a record date
+pub fn create_snapshot(program_id: &Pubkey, accounts: &[AccountInfo], args: SnapshotArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let snapshot_info = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to record a snapshot");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if args.total_shares == 0 {
+        msg!("Snapshot must hold some shares");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The tree is built off-chain, so at least make sure it can't hold more shares than exist
+    let supply = Mint::unpack(&fraction_mint.data.borrow())?.supply;
+    if args.total_shares > supply {
+        msg!("Snapshot holds {} shares but only {} exist", args.total_shares, supply);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.slot > Clock::get()?.slot {
+        msg!("Snapshot record date can't be in the future");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let id_bytes = args.id.to_le_bytes();
+    let snapshot_bump = assert_derivation(
+        program_id,
+        snapshot_info,
+        &[SNAPSHOT_PREFIX.as_bytes(), vault_info.key.as_ref(), &id_bytes],
+    )?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        snapshot_info,
+        system_program,
+        rent,
+        SNAPSHOT_SIZE,
+        &[SNAPSHOT_PREFIX.as_bytes(), vault_info.key.as_ref(), &id_bytes, &[snapshot_bump]],
+    )?;
+
+    let snapshot = Snapshot {
+        key: Key::SnapshotV1,
+        vault: *vault_info.key,
+        authority: *vault_authority.key,
+        id: args.id,
+        slot: args.slot,
+        merkle_root: args.merkle_root,
+        total_shares: args.total_shares,
+        payment_mint: Pubkey::default(),
+        payout_amount: 0,
+        bump: snapshot_bump,
+    };
+    snapshot.serialize(&mut &mut snapshot_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Deposit a payout for the holders in a snapshot. The first deposit picks the payment mint.
+pub fn fund_snapshot(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+
+    let authority_payment_account = next_account_info(accounts_iter)?;
+
+    let snapshot_info = next_account_info(accounts_iter)?;
+
+    let payout = next_account_info(accounts_iter)?;
+
+    let payment_mint = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !authority.is_signer {
+        msg!("Snapshot authority must sign to fund it");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(snapshot_info, program_id)?;
+    let mut snapshot = Snapshot::from_account_info(snapshot_info)?;
+
+    assert_vault_not_paused(program_id, &snapshot.vault, vault_pause)?;
+
+    if snapshot.authority != *authority.key {
+        msg!("Signer is not the snapshot authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let payout_bump = assert_derivation(
+        program_id,
+        payout,
+        &[SNAPSHOT_PAYOUT_PREFIX.as_bytes(), snapshot_info.key.as_ref()],
+    )?;
+
+    if payout.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        create_token_account(
+            authority,
+            payout,
+            payment_mint,
+            snapshot_info,
+            system_program,
+            rent_program,
+            rent,
+            &[SNAPSHOT_PAYOUT_PREFIX.as_bytes(), snapshot_info.key.as_ref(), &[payout_bump]],
+        )?;
+        snapshot.payment_mint = *payment_mint.key;
+    }
+
+    invoke(
+        &transfer(
+            &spl_token::id(),
+            authority_payment_account.key,
+            payout.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        accounts,
+    )?;
+
+    snapshot.payout_amount = snapshot
+        .payout_amount
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidArgument)?;
+    snapshot.serialize(&mut &mut snapshot_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Pay a holder their pro-rata share of a snapshot's payout, proving their balance with a Merkle proof
+pub fn claim_snapshot(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    balance: u64,
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let holder = next_account_info(accounts_iter)?;
+
+    let holder_payment_account = next_account_info(accounts_iter)?;
+
+    let snapshot_info = next_account_info(accounts_iter)?;
+
+    let claim_info = next_account_info(accounts_iter)?;
+
+    let payout = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !holder.is_signer {
+        msg!("Holder must sign to claim");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(snapshot_info, program_id)?;
+    let snapshot = Snapshot::from_account_info(snapshot_info)?;
+
+    assert_vault_not_paused(program_id, &snapshot.vault, vault_pause)?;
+
+    if !verify_merkle_proof(&proof, &snapshot.merkle_root, snapshot_leaf(holder.key, balance)) {
+        msg!("Invalid Merkle proof for {} holding {} shares", holder.key, balance);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_derivation(
+        program_id,
+        payout,
+        &[SNAPSHOT_PAYOUT_PREFIX.as_bytes(), snapshot_info.key.as_ref()],
+    )?;
+
+    let claim_bump = assert_derivation(
+        program_id,
+        claim_info,
+        &[SNAPSHOT_CLAIM_PREFIX.as_bytes(), snapshot_info.key.as_ref(), holder.key.as_ref()],
+    )?;
+    let mut claim = if claim_info.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        create_program_account(
+            program_id,
+            holder,
+            claim_info,
+            system_program,
+            rent,
+            SNAPSHOT_CLAIM_SIZE,
+            &[
+                SNAPSHOT_CLAIM_PREFIX.as_bytes(),
+                snapshot_info.key.as_ref(),
+                holder.key.as_ref(),
+                &[claim_bump],
+            ],
+        )?;
+        SnapshotClaim {
+            key: Key::SnapshotClaimV1,
+            snapshot: *snapshot_info.key,
+            holder: *holder.key,
+            claimed: 0,
+        }
+    } else {
+        assert_owned_by(claim_info, program_id)?;
+        SnapshotClaim::from_account_info(claim_info)?
+    };
+
+    let entitled = (balance as u128 * snapshot.payout_amount as u128 / snapshot.total_shares as u128) as u64;
+    let owed = entitled.saturating_sub(claim.claimed);
+    if owed == 0 {
+        msg!("Nothing left to claim");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    let id_bytes = snapshot.id.to_le_bytes();
+    invoke_signed(
+        &transfer(
+            &spl_token::id(),
+            payout.key,
+            holder_payment_account.key,
+            snapshot_info.key,
+            &[],
+            owed,
+        )?,
+        accounts,
+        &[&[
+            SNAPSHOT_PREFIX.as_bytes(),
+            snapshot.vault.as_ref(),
+            &id_bytes,
+            &[snapshot.bump],
+        ]],
+    )?;
+
+    claim.claimed = entitled;
+    claim.serialize(&mut &mut claim_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Put a vault under shareholder governance with the given quorum and approval threshold
+pub fn create_governance(program_id: &Pubkey, accounts: &[AccountInfo], args: GovernanceArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let governance_info = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let token_vault_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to create governance");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if args.quorum_bps > 10_000 || args.threshold_bps > 10_000 || args.voting_period <= 0 {
+        msg!("Quorum and threshold are basis points and the voting period must be positive");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let governance_bump = assert_derivation(
+        program_id,
+        governance_info,
+        &[GOVERNANCE_PREFIX.as_bytes(), vault_info.key.as_ref()],
+    )?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        governance_info,
+        system_program,
+        rent,
+        GOVERNANCE_SIZE,
+        &[GOVERNANCE_PREFIX.as_bytes(), vault_info.key.as_ref(), &[governance_bump]],
+    )?;
+
+    let governance = Governance {
+        key: Key::GovernanceV1,
+        vault: *vault_info.key,
+        authority: *vault_authority.key,
+        fraction_mint: vault.fraction_mint,
+        quorum_bps: args.quorum_bps,
+        threshold_bps: args.threshold_bps,
+        voting_period: args.voting_period,
+        proposal_count: 0,
+        bump: governance_bump,
+    };
+    governance.serialize(&mut &mut governance_info.data.borrow_mut()[..])?;
+
+    // Hand the vault to the Governance account, so only a passed proposal can act as its authority
+    invoke(
+        &create_set_authority_instruction(
+            *token_vault_program.key,
+            *vault_info.key,
+            *vault_authority.key,
+            *governance_info.key,
+        ),
+        accounts,
+    )?;
+    if load_vault(vault_info)?.authority != *governance_info.key {
+        msg!("Vault authority was not handed to the governance");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
+/// Open a proposal for holders to vote on, voting starts immediately. Quorum is measured against the
+/// shares outstanding now, which leaves out shares still in the fraction treasury or held for an offering.
+pub fn create_proposal(program_id: &Pubkey, accounts: &[AccountInfo], action: ProposalAction) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let proposer = next_account_info(accounts_iter)?;
+
+    let governance_info = next_account_info(accounts_iter)?;
+
+    let proposal_info = next_account_info(accounts_iter)?;
+
+    let vote_escrow = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let offering_shares = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !proposer.is_signer {
+        msg!("Proposer must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(governance_info, program_id)?;
+    let mut governance = Governance::from_account_info(governance_info)?;
+
+    if governance.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this governance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if governance.vault != *vault_info.key {
+        msg!("Vault does not belong to this governance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if governance.voting_period <= 0 {
+        msg!("Governance voting period must be positive");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let end_ts = now.checked_add(governance.voting_period).ok_or(ProgramError::InvalidArgument)?;
+
+    let vault = load_vault(vault_info)?;
+    if vault.fraction_treasury != *fraction_treasury.key {
+        msg!("Fraction treasury does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (offering, _) =
+        Pubkey::find_program_address(&[OFFERING_PREFIX.as_bytes(), vault_info.key.as_ref()], program_id);
+    assert_derivation(
+        program_id,
+        offering_shares,
+        &[OFFERING_SHARES_PREFIX.as_bytes(), offering.as_ref()],
+    )?;
+
+    // Shares waiting in an offering's escrow can't vote, whether they are unsold or reserved for a buyer
+    let supply = Mint::unpack(&fraction_mint.data.borrow())?.supply;
+    let unissued = Account::unpack(&fraction_treasury.data.borrow())?.amount;
+    let offered = Account::unpack(&offering_shares.data.borrow()).map_or(0, |escrow| escrow.amount);
+    let quorum_base = supply.saturating_sub(unissued).saturating_sub(offered);
+
+    let index_bytes = governance.proposal_count.to_le_bytes();
+    let proposal_bump = assert_derivation(
+        program_id,
+        proposal_info,
+        &[PROPOSAL_PREFIX.as_bytes(), governance_info.key.as_ref(), &index_bytes],
+    )?;
+    let escrow_bump = assert_derivation(
+        program_id,
+        vote_escrow,
+        &[VOTE_ESCROW_PREFIX.as_bytes(), proposal_info.key.as_ref()],
+    )?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        proposer,
+        proposal_info,
+        system_program,
+        rent,
+        PROPOSAL_SIZE,
+        &[
+            PROPOSAL_PREFIX.as_bytes(),
+            governance_info.key.as_ref(),
+            &index_bytes,
+            &[proposal_bump],
+        ],
+    )?;
+
+    // Create the Vote Escrow that locks voters' shares until voting ends
+    create_token_account(
+        proposer,
+        vote_escrow,
+        fraction_mint,
+        proposal_info,
+        system_program,
+        rent_program,
+        rent,
+        &[VOTE_ESCROW_PREFIX.as_bytes(), proposal_info.key.as_ref(), &[escrow_bump]],
+    )?;
+
+    let proposal = Proposal {
+        key: Key::ProposalV1,
+        governance: *governance_info.key,
+        proposer: *proposer.key,
+        index: governance.proposal_count,
+        action,
+        start_ts: now,
+        end_ts,
+        yes_votes: 0,
+        no_votes: 0,
+        state: ProposalState::Voting,
+        bump: proposal_bump,
+        quorum_base,
+    };
+    proposal.serialize(&mut &mut proposal_info.data.borrow_mut()[..])?;
+
+    governance.proposal_count += 1;
+    governance.serialize(&mut &mut governance_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Vote on a proposal with `amount` shares, which are locked in the vote escrow until voting ends
+pub fn cast_vote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    approve: bool,
+    amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let voter = next_account_info(accounts_iter)?;
+
+    let voter_share_account = next_account_info(accounts_iter)?;
+
+    let proposal_info = next_account_info(accounts_iter)?;
+
+    let vote_info = next_account_info(accounts_iter)?;
+
+    let vote_escrow = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let governance_info = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !voter.is_signer {
+        msg!("Voter must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(proposal_info, program_id)?;
+    let mut proposal = Proposal::from_account_info(proposal_info)?;
+
+    assert_owned_by(governance_info, program_id)?;
+    let governance = Governance::from_account_info(governance_info)?;
+    if proposal.governance != *governance_info.key {
+        msg!("Proposal does not belong to this governance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_vault_not_paused(program_id, &governance.vault, vault_pause)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if proposal.state != ProposalState::Voting || now >= proposal.end_ts {
+        msg!("Voting on this proposal ended at {}", proposal.end_ts);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if amount == 0 {
+        msg!("Must vote with some shares");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_derivation(
+        program_id,
+        vote_escrow,
+        &[VOTE_ESCROW_PREFIX.as_bytes(), proposal_info.key.as_ref()],
+    )?;
+    let vote_bump = assert_derivation(
+        program_id,
+        vote_info,
+        &[VOTE_PREFIX.as_bytes(), proposal_info.key.as_ref(), voter.key.as_ref()],
+    )?;
+
+    let mut vote = if vote_info.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        create_program_account(
+            program_id,
+            voter,
+            vote_info,
+            system_program,
+            rent,
+            VOTE_RECORD_SIZE,
+            &[
+                VOTE_PREFIX.as_bytes(),
+                proposal_info.key.as_ref(),
+                voter.key.as_ref(),
+                &[vote_bump],
+            ],
+        )?;
+        VoteRecord {
+            key: Key::VoteRecordV1,
+            proposal: *proposal_info.key,
+            voter: *voter.key,
+            approve,
+            amount: 0,
+        }
+    } else {
+        assert_owned_by(vote_info, program_id)?;
+        VoteRecord::from_account_info(vote_info)?
+    };
+
+    if vote.approve != approve {
+        msg!("Already voted the other way on this proposal");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Lock the shares
+    let balance_before = share_balance(voter_share_account);
+    invoke(
+        &transfer(
+            &spl_token::id(),
+            voter_share_account.key,
+            vote_escrow.key,
+            voter.key,
+            &[],
+            amount,
+        )?,
+        accounts,
+    )?;
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        voter_share_account,
+        balance_before,
+        false,
+        None,
+    )?;
+
+    vote.amount += amount;
+    vote.serialize(&mut &mut vote_info.data.borrow_mut()[..])?;
+
+    if approve {
+        proposal.yes_votes += amount;
+    } else {
+        proposal.no_votes += amount;
+    }
+    proposal.serialize(&mut &mut proposal_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Tally a proposal once voting has ended, against the shares outstanding when it was created
+pub fn finalize_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let governance_info = next_account_info(accounts_iter)?;
+
+    let proposal_info = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_owned_by(governance_info, program_id)?;
+    let governance = Governance::from_account_info(governance_info)?;
+
+    assert_vault_not_paused(program_id, &governance.vault, vault_pause)?;
+
+    assert_owned_by(proposal_info, program_id)?;
+    let mut proposal = Proposal::from_account_info(proposal_info)?;
+
+    if proposal.governance != *governance_info.key {
+        msg!("Proposal does not belong to this governance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    if proposal.state != ProposalState::Voting || now < proposal.end_ts {
+        msg!("Proposal can only be finalized once voting ends at {}", proposal.end_ts);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (quorum_met, threshold_met) = proposal.tally(&governance);
+
+    msg!(
+        "Yes: {}, No: {}, Outstanding: {}, Quorum met: {}, Threshold met: {}",
+        proposal.yes_votes,
+        proposal.no_votes,
+        proposal.quorum_base,
+        quorum_met,
+        threshold_met
+    );
+
+    proposal.state = if quorum_met && threshold_met {
+        ProposalState::Succeeded
+    } else {
+        ProposalState::Defeated
+    };
+    proposal.serialize(&mut &mut proposal_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Return a voter's locked shares once voting has ended
+pub fn withdraw_vote(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let voter = next_account_info(accounts_iter)?;
+
+    let voter_share_account = next_account_info(accounts_iter)?;
+
+    let proposal_info = next_account_info(accounts_iter)?;
+
+    let vote_info = next_account_info(accounts_iter)?;
+
+    let vote_escrow = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let governance_info = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !voter.is_signer {
+        msg!("Voter must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(proposal_info, program_id)?;
+    let proposal = Proposal::from_account_info(proposal_info)?;
+
+    assert_owned_by(governance_info, program_id)?;
+    let governance = Governance::from_account_info(governance_info)?;
+    if proposal.governance != *governance_info.key {
+        msg!("Proposal does not belong to this governance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_vault_not_paused(program_id, &governance.vault, vault_pause)?;
+
+    if governance.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this governance");
+        return Err(ProgramError::InvalidArgument);
+    }
+    assert_share_owner(voter_share_account, voter.key, fraction_mint.key)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if now < proposal.end_ts {
+        msg!("Shares are locked until voting ends at {}", proposal.end_ts);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_owned_by(vote_info, program_id)?;
+    assert_derivation(
+        program_id,
+        vote_info,
+        &[VOTE_PREFIX.as_bytes(), proposal_info.key.as_ref(), voter.key.as_ref()],
+    )?;
+    let vote = VoteRecord::from_account_info(vote_info)?;
+
+    assert_derivation(
+        program_id,
+        vote_escrow,
+        &[VOTE_ESCROW_PREFIX.as_bytes(), proposal_info.key.as_ref()],
+    )?;
+
+    let index_bytes = proposal.index.to_le_bytes();
+    let balance_before = share_balance(voter_share_account);
+    invoke_signed(
+        &transfer(
+            &spl_token::id(),
+            vote_escrow.key,
+            voter_share_account.key,
+            proposal_info.key,
+            &[],
+            vote.amount,
+        )?,
+        accounts,
+        &[&[
+            PROPOSAL_PREFIX.as_bytes(),
+            proposal.governance.as_ref(),
+            &index_bytes,
+            &[proposal.bump],
+        ]],
+    )?;
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        voter_share_account,
+        balance_before,
+        false,
+        None,
+    )?;
+
+    close_program_account(vote_info, voter)?;
+
+    Ok(())
+}
+
+/// Carry out a passed proposal. The vault authority still signs, and the token vault program only checks
+/// its signature, so holders' approval only binds a vault authority that acts through this instruction.
+pub fn execute_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let executor = next_account_info(accounts_iter)?;
+
+    let governance_info = next_account_info(accounts_iter)?;
+
+    let proposal_info = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let token_vault_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    assert_vault_program(program_id, config_info, token_vault_program)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !executor.is_signer {
+        msg!("Executor must sign to execute a proposal");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_owned_by(governance_info, program_id)?;
+    let governance = Governance::from_account_info(governance_info)?;
+
+    assert_owned_by(proposal_info, program_id)?;
+    let mut proposal = Proposal::from_account_info(proposal_info)?;
+
+    if proposal.governance != *governance_info.key || governance.vault != *vault_info.key {
+        msg!("Proposal does not belong to this vault's governance");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if proposal.state != ProposalState::Succeeded {
+        msg!("Only a passed proposal can be executed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    match proposal.action {
+        ProposalAction::UpdateExternalPrice {
+            price_per_share,
+            allowed_to_combine,
+        } => {
+            let external_pricing_acct = next_account_info(accounts_iter)?;
+
+            let portfolio_info = next_account_info(accounts_iter)?;
+
+            // The External Pricing account is a PDA of this program, seeded with whoever created the vault
+            assert_owned_by(portfolio_info, program_id)?;
+            assert_derivation(
+                program_id,
+                portfolio_info,
+                &[PORTFOLIO_PREFIX.as_bytes(), vault_info.key.as_ref()],
+            )?;
+            let portfolio = Portfolio::from_account_info(portfolio_info)?;
+            let external_bump = assert_derivation(
+                program_id,
+                external_pricing_acct,
+                &[b"external", vault_info.key.as_ref(), portfolio.payer.as_ref()],
+            )?;
+
+            invoke_signed(
+                &create_update_external_price_account_instruction(
+                    *token_vault_program.key,
+                    *external_pricing_acct.key,
+                    price_per_share,
+                    spl_token::native_mint::ID,
+                    allowed_to_combine,
+                ),
+                accounts,
+                &[&[
+                    b"external",
+                    vault_info.key.as_ref(),
+                    portfolio.payer.as_ref(),
+                    &[external_bump],
+                ]],
+            )?;
+        }
+        ProposalAction::CombineVault => {
+            let outstanding_share_token_account = next_account_info(accounts_iter)?;
+
+            let paying_token_account = next_account_info(accounts_iter)?;
+
+            let fraction_mint = next_account_info(accounts_iter)?;
+
+            let fraction_treasury = next_account_info(accounts_iter)?;
+
+            let redeem_treasury = next_account_info(accounts_iter)?;
+
+            let new_vault_authority = next_account_info(accounts_iter)?;
+
+            let paying_transfer_authority = next_account_info(accounts_iter)?;
+
+            let fraction_burn_authority = next_account_info(accounts_iter)?;
+
+            let external_pricing_acct = next_account_info(accounts_iter)?;
+
+            let new_authority_denied = next_account_info(accounts_iter)?;
+
+            let _token_program = next_account_info(accounts_iter)?;
+
+            let fee_account = next_account_info(accounts_iter)?;
+
+            // The new vault authority redeems the rNFTs
+            assert_not_denied(program_id, new_vault_authority.key, new_authority_denied)?;
+
+            // The buyer pays the protocol's sale fee on top of what the combine charges them for the
+            // shares they don't hold, worked out the same way
+            let external_pricing = ExternalPriceAccount::from_account_info(external_pricing_acct)?;
+            let shares_bought = Mint::unpack(&fraction_mint.data.borrow())?
+                .supply
+                .saturating_sub(Account::unpack(&fraction_treasury.data.borrow())?.amount)
+                .saturating_sub(Account::unpack(&outstanding_share_token_account.data.borrow())?.amount);
+            let buyout_price = shares_bought.saturating_mul(external_pricing.price_per_share);
+            let fee = sale_fee(load_config(program_id, config_info)?.as_ref(), buyout_price);
+            if fee > 0 {
+                assert_fee_account(program_id, fee_account, &external_pricing.price_mint)?;
+                invoke(
+                    &transfer(
+                        &spl_token::id(),
+                        paying_token_account.key,
+                        fee_account.key,
+                        paying_transfer_authority.key,
+                        &[],
+                        fee,
+                    )?,
+                    accounts,
+                )?;
+            }
+
+            // The Governance account holds the vault's authority and signs for the passed proposal
+            invoke_signed(
+                &create_combine_vault_instruction(
+                    *token_vault_program.key,
+                    *vault_info.key,
+                    *outstanding_share_token_account.key,
+                    *paying_token_account.key,
+                    *fraction_mint.key,
+                    *fraction_treasury.key,
+                    *redeem_treasury.key,
+                    *new_vault_authority.key,
+                    *governance_info.key,
+                    *paying_transfer_authority.key,
+                    *fraction_burn_authority.key,
+                    *external_pricing_acct.key,
+                ),
+                accounts,
+                &[&[
+                    GOVERNANCE_PREFIX.as_bytes(),
+                    vault_info.key.as_ref(),
+                    &[governance.bump],
+                ]],
+            )?;
+        }
+    }
+
+    proposal.state = ProposalState::Executed;
+    proposal.serialize(&mut &mut proposal_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Close a proposal that was defeated or executed once every vote has been withdrawn, returning the rent of
+/// the proposal and its vote escrow to the proposer. Anyone can crank this.
+pub fn close_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let proposer = next_account_info(accounts_iter)?;
+
+    let proposal_info = next_account_info(accounts_iter)?;
+
+    let vote_escrow = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_owned_by(proposal_info, program_id)?;
+    let proposal = Proposal::from_account_info(proposal_info)?;
+
+    if proposal.proposer != *proposer.key {
+        msg!("Rent can only be returned to the proposer {}", proposal.proposer);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if proposal.state != ProposalState::Defeated && proposal.state != ProposalState::Executed {
+        msg!("Only a defeated or executed proposal can be closed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    assert_derivation(
+        program_id,
+        vote_escrow,
+        &[VOTE_ESCROW_PREFIX.as_bytes(), proposal_info.key.as_ref()],
+    )?;
+    let locked = Account::unpack(&vote_escrow.data.borrow())?.amount;
+    if locked > 0 {
+        msg!("{} voted shares must be withdrawn first", locked);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let index_bytes = proposal.index.to_le_bytes();
+    invoke_signed(
+        &close_account(
+            &spl_token::id(),
+            vote_escrow.key,
+            proposer.key,
+            proposal_info.key,
+            &[],
+        )?,
+        accounts,
+        &[&[
+            PROPOSAL_PREFIX.as_bytes(),
+            proposal.governance.as_ref(),
+            &index_bytes,
+            &[proposal.bump],
+        ]],
+    )?;
+
+    close_program_account(proposal_info, proposer)?;
+
+    Ok(())
+}
+
+/// Start restricting a vault's shares to verified investors, managed by the compliance authority
+pub fn create_allowlist(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let allowlist_info = next_account_info(accounts_iter)?;
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign to create an allowlist");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let allowlist_bump = assert_derivation(
+        program_id,
+        allowlist_info,
+        &[ALLOWLIST_PREFIX.as_bytes(), vault_info.key.as_ref()],
+    )?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        allowlist_info,
+        system_program,
+        rent,
+        ALLOWLIST_SIZE,
+        &[ALLOWLIST_PREFIX.as_bytes(), vault_info.key.as_ref(), &[allowlist_bump]],
+    )?;
+
+    let allowlist = Allowlist {
+        key: Key::AllowlistV1,
+        vault: *vault_info.key,
+        compliance_authority: *compliance_authority.key,
+        attester: Pubkey::default(),
+        bump: allowlist_bump,
+    };
+    allowlist.serialize(&mut &mut allowlist_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Add a verified wallet to the allowlist, or renew its verification
+pub fn set_allowlist_entry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: AllowlistEntryArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let allowlist_info = next_account_info(accounts_iter)?;
+
+    let entry_info = next_account_info(accounts_iter)?;
+
+    let wallet = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let compliance_role = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, compliance_role, compliance_authority.key, Role::Compliance)?;
+
+    let allowlist = load_allowlist(program_id, allowlist_info, compliance_authority)?;
+
+    assert_vault_not_paused(program_id, &allowlist.vault, vault_pause)?;
+
+    if args.expires_ts <= Clock::get()?.unix_timestamp {
+        msg!("Allowlist entry would already be expired");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let entry_bump = assert_derivation(
+        program_id,
+        entry_info,
+        &[ALLOWLIST_ENTRY_PREFIX.as_bytes(), allowlist_info.key.as_ref(), wallet.key.as_ref()],
+    )?;
+
+    if entry_info.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        create_program_account(
+            program_id,
+            compliance_authority,
+            entry_info,
+            system_program,
+            rent,
+            ALLOWLIST_ENTRY_SIZE,
+            &[
+                ALLOWLIST_ENTRY_PREFIX.as_bytes(),
+                allowlist_info.key.as_ref(),
+                wallet.key.as_ref(),
+                &[entry_bump],
+            ],
+        )?;
+    } else {
+        assert_owned_by(entry_info, program_id)?;
+    }
+
+    let entry = AllowlistEntry {
+        key: Key::AllowlistEntryV1,
+        allowlist: *allowlist_info.key,
+        wallet: *wallet.key,
+        expires_ts: args.expires_ts,
+        accredited: args.accredited,
+        country: args.country,
+    };
+    entry.serialize(&mut &mut entry_info.data.borrow_mut()[..])?;
+
+    msg!("{} allowed for vault {} until {}", wallet.key, allowlist.vault, args.expires_ts);
+
+    Ok(())
+}
+
+/// Register the key whose off-chain KYC Attestations the allowlist accepts in place of entries.
+/// Passing the default pubkey (the system program) stops accepting attestations.
+pub fn set_attester(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let allowlist_info = next_account_info(accounts_iter)?;
+
+    let attester = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let compliance_role = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, compliance_role, compliance_authority.key, Role::Compliance)?;
+
+    let mut allowlist = load_allowlist(program_id, allowlist_info, compliance_authority)?;
+
+    assert_vault_not_paused(program_id, &allowlist.vault, vault_pause)?;
+
+    allowlist.attester = *attester.key;
+    allowlist.serialize(&mut &mut allowlist_info.data.borrow_mut()[..])?;
+
+    msg!("Attester of vault {} is {}", allowlist.vault, attester.key);
+
+    Ok(())
+}
+
+/// Take a wallet off the allowlist. Shares it already holds are not affected.
+pub fn remove_allowlist_entry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let allowlist_info = next_account_info(accounts_iter)?;
+
+    let entry_info = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let compliance_role = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, compliance_role, compliance_authority.key, Role::Compliance)?;
+
+    let allowlist = load_allowlist(program_id, allowlist_info, compliance_authority)?;
+
+    assert_vault_not_paused(program_id, &allowlist.vault, vault_pause)?;
+
+    assert_owned_by(entry_info, program_id)?;
+    let entry = AllowlistEntry::from_account_info(entry_info)?;
+    if entry.allowlist != *allowlist_info.key {
+        msg!("Entry does not belong to this allowlist");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    close_program_account(entry_info, compliance_authority)?;
+
+    Ok(())
+}
+
+fn load_allowlist(
+    program_id: &Pubkey,
+    allowlist_info: &AccountInfo,
+    compliance_authority: &AccountInfo,
+) -> Result<Allowlist, ProgramError> {
+    assert_owned_by(allowlist_info, program_id)?;
+    let allowlist = Allowlist::from_account_info(allowlist_info)?;
+
+    if !compliance_authority.is_signer || allowlist.compliance_authority != *compliance_authority.key {
+        msg!("Compliance authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    Ok(allowlist)
+}
+
+/// Check the wallet may receive the vault's shares. Vaults without an Allowlist are unrestricted unless
+/// their Compliance Profile says otherwise, but the allowlist account is still derived so a caller can't
+/// skip the check by passing another account. A wallet without an entry can instead be verified by a
+/// KYC Attestation in the transaction, if the caller passes the instructions sysvar.
+fn assert_allowlisted(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    profile: Option<&ComplianceProfile>,
+    allowlist_info: &AccountInfo,
+    entry_info: &AccountInfo,
+    wallet: &Pubkey,
+    instructions_sysvar: Option<&AccountInfo>,
+) -> ProgramResult {
+    assert_derivation(program_id, allowlist_info, &[ALLOWLIST_PREFIX.as_bytes(), vault.as_ref()])?;
+    if allowlist_info.data_is_empty() {
+        if profile.map_or(false, |profile| profile.requires_allowlist()) {
+            msg!("Compliance profile of vault {} requires an allowlist", vault);
+            return Err(ProgramError::InvalidArgument);
+        }
+        return Ok(());
+    }
+
+    assert_derivation(
+        program_id,
+        entry_info,
+        &[ALLOWLIST_ENTRY_PREFIX.as_bytes(), allowlist_info.key.as_ref(), wallet.as_ref()],
+    )?;
+    let verification = if entry_info.data_is_empty() {
+        let attestation = match instructions_sysvar {
+            Some(instructions_sysvar) => {
+                find_attestation(program_id, allowlist_info, instructions_sysvar, vault, wallet)?
+            }
+            None => None,
+        };
+        match attestation {
+            Some(attestation) => attestation,
+            None => {
+                msg!("{} is not on the allowlist for vault {}", wallet, vault);
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+    } else {
+        assert_owned_by(entry_info, program_id)?;
+        let entry = AllowlistEntry::from_account_info(entry_info)?;
+        KycAttestation {
+            vault: *vault,
+            wallet: *wallet,
+            expires_ts: entry.expires_ts,
+            accredited: entry.accredited,
+            country: entry.country,
+        }
+    };
+
+    if verification.expires_ts <= Clock::get()?.unix_timestamp {
+        msg!("Verification of {} expired at {}", wallet, verification.expires_ts);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if let Some(profile) = profile {
+        if profile.accredited_only && !verification.accredited {
+            msg!("{} is not an accredited investor", wallet);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !profile.allowed_countries.is_empty() && !profile.allowed_countries.contains(&verification.country) {
+            msg!(
+                "{} resides in {}, which the vault's compliance profile does not allow",
+                wallet,
+                String::from_utf8_lossy(&verification.country)
+            );
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    Ok(())
+}
+
+/// The latest KYC Attestation of the wallet for the vault signed by the allowlist's attester, if any
+fn find_attestation(
+    program_id: &Pubkey,
+    allowlist_info: &AccountInfo,
+    instructions_sysvar: &AccountInfo,
+    vault: &Pubkey,
+    wallet: &Pubkey,
+) -> Result<Option<KycAttestation>, ProgramError> {
+    assert_owned_by(allowlist_info, program_id)?;
+    let allowlist = Allowlist::from_account_info(allowlist_info)?;
+    if allowlist.attester == Pubkey::default() {
+        return Ok(None);
+    }
+
+    let attestation = ed25519_signed_messages(instructions_sysvar, &allowlist.attester)?
+        .iter()
+        .filter_map(|message| KycAttestation::try_from_slice(message).ok())
+        .filter(|attestation| attestation.vault == *vault && attestation.wallet == *wallet)
+        .max_by_key(|attestation| attestation.expires_ts);
+
+    Ok(attestation)
+}
+
+/// Move shares between two allowlisted holders. The fraction mint's freeze authority has to stay with the
+/// token vault program, so holders can still bypass this with a plain SPL token transfer.
+pub fn transfer_shares(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    number_of_shares: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let sender = next_account_info(accounts_iter)?;
+
+    let sender_share_account = next_account_info(accounts_iter)?;
+
+    let destination = next_account_info(accounts_iter)?;
+
+    let destination_ata = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let allowlist = next_account_info(accounts_iter)?;
+
+    let sender_entry = next_account_info(accounts_iter)?;
+
+    let destination_entry = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let vault_compliance = next_account_info(accounts_iter)?;
+
+    let compliance_profile = next_account_info(accounts_iter)?;
+
+    let sender_denied = next_account_info(accounts_iter)?;
+
+    let destination_denied = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    if !sender.is_signer {
+        msg!("Sender must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_not_denied(program_id, sender.key, sender_denied)?;
+    assert_not_denied(program_id, destination.key, destination_denied)?;
+
+    let vault = load_vault(vault_info)?;
+    if vault.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let compliance = load_compliance_profile(program_id, vault_info.key, vault_compliance, compliance_profile)?;
+    let profile = match compliance {
+        Some((compliance, profile)) => {
+            let lockup_ends = compliance.created_ts.saturating_add(profile.lockup_duration);
+            if Clock::get()?.unix_timestamp < lockup_ends {
+                msg!("Shares of this vault are locked up until {}", lockup_ends);
+                return Err(ProgramError::InvalidArgument);
+            }
+            Some(profile)
+        }
+        None => None,
+    };
+
+    assert_allowlisted(
+        program_id,
+        vault_info.key,
+        profile.as_ref(),
+        allowlist,
+        sender_entry,
+        sender.key,
+        None,
+    )?;
+    assert_allowlisted(
+        program_id,
+        vault_info.key,
+        profile.as_ref(),
+        allowlist,
+        destination_entry,
+        destination.key,
+        None,
+    )?;
+
+    if Account::unpack(&destination_ata.data.borrow()).is_err() {
+        invoke(
+            &create_associated_token_account(sender.key, destination.key, fraction_mint.key),
+            &[
+                sender.clone(),
+                destination_ata.clone(),
+                destination.clone(),
+                fraction_mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_program.clone(),
+            ],
+        )?;
+    }
+    assert_share_owner(sender_share_account, sender.key, fraction_mint.key)?;
+    assert_share_owner(destination_ata, destination.key, fraction_mint.key)?;
+
+    let sender_before = share_balance(sender_share_account);
+    let destination_before = share_balance(destination_ata);
+
+    invoke(
+        &transfer(
+            &spl_token::id(),
+            sender_share_account.key,
+            destination_ata.key,
+            sender.key,
+            &[],
+            number_of_shares,
+        )?,
+        accounts,
+    )?;
+
+    // Count the sender out first, so a holder passing on all their shares frees their place for the destination
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        sender_share_account,
+        sender_before,
+        true,
+        profile.as_ref(),
+    )?;
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        destination_ata,
+        destination_before,
+        true,
+        profile.as_ref(),
+    )
+}
+
+/// Release whatever has vested since the last claim to the recipient. Once everything is claimed
+/// the vesting accounts are closed and their rent goes back to whoever paid for them.
+pub fn claim_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let recipient = next_account_info(accounts_iter)?;
+
+    let recipient_ata = next_account_info(accounts_iter)?;
+
+    let vesting_info = next_account_info(accounts_iter)?;
+
+    let vesting_shares = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let rent_receiver = next_account_info(accounts_iter)?;
+
+    let allowlist = next_account_info(accounts_iter)?;
+
+    let allowlist_entry = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let vault_compliance = next_account_info(accounts_iter)?;
+
+    let compliance_profile = next_account_info(accounts_iter)?;
+
+    let recipient_denied = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_not_denied(program_id, recipient.key, recipient_denied)?;
+
+    assert_owned_by(vesting_info, program_id)?;
+    let mut vesting = Vesting::from_account_info(vesting_info)?;
+
+    assert_vault_not_paused(program_id, &vesting.vault, vault_pause)?;
+
+    if vesting.recipient != *recipient.key
+        || vesting.fraction_mint != *fraction_mint.key
+        || vesting.payer != *rent_receiver.key
+    {
+        msg!("Recipient, fraction mint or rent receiver does not match the vesting account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Anyone can crank the claim, so the shares may only go to the recipient's own account
+    if *recipient_ata.key != get_associated_token_address(&vesting.recipient, fraction_mint.key) {
+        msg!("Vested shares can only be claimed to the recipient's associated token account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_derivation(
+        program_id,
+        vesting_shares,
+        &[VESTING_SHARES_PREFIX.as_bytes(), vesting_info.key.as_ref()],
+    )?;
+
+    let profile = load_compliance_profile(program_id, &vesting.vault, vault_compliance, compliance_profile)?
+        .map(|(_, profile)| profile);
+
+    assert_allowlisted(
+        program_id,
+        &vesting.vault,
+        profile.as_ref(),
+        allowlist,
+        allowlist_entry,
+        recipient.key,
+        None,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let claimable = vesting.schedule.vested(vesting.total, now) - vesting.claimed;
+    if claimable == 0 {
+        msg!("Nothing has vested since the last claim");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    if Account::unpack(&recipient_ata.data.borrow()).is_err() {
+        invoke(
+            &create_associated_token_account(payer.key, recipient.key, fraction_mint.key),
+            &[
+                payer.clone(),
+                recipient_ata.clone(),
+                recipient.clone(),
+                fraction_mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_program.clone(),
+            ],
+        )?;
+    }
+
+    let vesting_signer_seeds: &[&[u8]] = &[
+        VESTING_PREFIX.as_bytes(),
+        vesting.vault.as_ref(),
+        vesting.recipient.as_ref(),
+        &[vesting.bump],
+    ];
+
+    let balance_before = share_balance(recipient_ata);
+    invoke_signed(
+        &transfer(
+            &spl_token::id(),
+            vesting_shares.key,
+            recipient_ata.key,
+            vesting_info.key,
+            &[],
+            claimable,
+        )?,
+        accounts,
+        &[vesting_signer_seeds],
+    )?;
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        recipient_ata,
+        balance_before,
+        true,
+        profile.as_ref(),
+    )?;
+
+    vesting.claimed += claimable;
+    msg!("Claimed {} of {} vesting shares", vesting.claimed, vesting.total);
+
+    if vesting.claimed == vesting.total {
+        invoke_signed(
+            &close_account(
+                &spl_token::id(),
+                vesting_shares.key,
+                rent_receiver.key,
+                vesting_info.key,
+                &[],
+            )?,
+            accounts,
+            &[vesting_signer_seeds],
+        )?;
+        close_program_account(vesting_info, rent_receiver)?;
+    } else {
+        vesting.serialize(&mut &mut vesting_info.data.borrow_mut()[..])?;
+    }
+
+    Ok(())
+}
+
+/// Start counting a vault's holders, optionally capping how many there can be. The count starts at zero,
+/// so it has to be created before any shares leave the fraction treasury.
+pub fn create_holder_registry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: HolderRegistryArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let registry_info = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let fraction_treasury = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    let vault = load_vault_as_authority(vault_info, vault_authority)?;
+
+    if vault.fraction_mint != *fraction_mint.key || vault.fraction_treasury != *fraction_treasury.key {
+        msg!("Fraction mint or treasury does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let supply = Mint::unpack(&fraction_mint.data.borrow())?.supply;
+    let unissued = Account::unpack(&fraction_treasury.data.borrow())?.amount;
+    if supply != unissued {
+        msg!("{} shares have already left the fraction treasury, so their holders can't be counted", supply - unissued);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let registry_bump = assert_derivation(
+        program_id,
+        registry_info,
+        &[HOLDER_REGISTRY_PREFIX.as_bytes(), vault.fraction_mint.as_ref()],
+    )?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        registry_info,
+        system_program,
+        rent,
+        HOLDER_REGISTRY_SIZE,
+        &[HOLDER_REGISTRY_PREFIX.as_bytes(), vault.fraction_mint.as_ref(), &[registry_bump]],
+    )?;
+
+    let registry = HolderRegistry {
+        key: Key::HolderRegistryV1,
+        vault: *vault_info.key,
+        fraction_mint: vault.fraction_mint,
+        max_holders: args.max_holders,
+        holder_count: 0,
+        bump: registry_bump,
+    };
+    registry.serialize(&mut &mut registry_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Change a vault's holder limit. Lowering it below the current count only stops new holders.
+pub fn set_max_holders(program_id: &Pubkey, accounts: &[AccountInfo], max_holders: u64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let vault_authority = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let registry_info = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    load_vault_as_authority(vault_info, vault_authority)?;
+
+    assert_owned_by(registry_info, program_id)?;
+    let mut registry = HolderRegistry::from_account_info(registry_info)?;
+    if registry.vault != *vault_info.key {
+        msg!("Holder registry does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    registry.max_holders = max_holders;
+    registry.serialize(&mut &mut registry_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Set up the rules of a jurisdiction for vaults to follow. Anyone can create profiles, a vault
+/// chooses which one it follows when it is created.
+pub fn create_compliance_profile(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ComplianceProfileArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let authority = next_account_info(accounts_iter)?;
+
+    let profile_info = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let compliance_role = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, compliance_role, authority.key, Role::Compliance)?;
+
+    if !authority.is_signer {
+        msg!("Profile authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let id_bytes = args.id.to_le_bytes();
+    let profile_bump = assert_derivation(
+        program_id,
+        profile_info,
+        &[COMPLIANCE_PROFILE_PREFIX.as_bytes(), authority.key.as_ref(), &id_bytes],
+    )?;
+
+    let profile = compliance_profile_from_args(*authority.key, args, profile_bump)?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        profile_info,
+        system_program,
+        rent,
+        COMPLIANCE_PROFILE_SIZE,
+        &[COMPLIANCE_PROFILE_PREFIX.as_bytes(), authority.key.as_ref(), &id_bytes, &[profile_bump]],
+    )?;
+
+    profile.serialize(&mut &mut profile_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Change the rules of a profile, which applies to every vault following it straight away.
+pub fn update_compliance_profile(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ComplianceProfileArgs,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let authority = next_account_info(accounts_iter)?;
+
+    let profile_info = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_owned_by(profile_info, program_id)?;
+    let current = ComplianceProfile::from_account_info(profile_info)?;
+
+    if !authority.is_signer || current.authority != *authority.key {
+        msg!("Profile authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if current.id != args.id {
+        msg!("Profile id can't be changed");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let profile = compliance_profile_from_args(current.authority, args, current.bump)?;
+    profile.serialize(&mut &mut profile_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn compliance_profile_from_args(
+    authority: Pubkey,
+    args: ComplianceProfileArgs,
+    bump: u8,
+) -> Result<ComplianceProfile, ProgramError> {
+    if args.allowed_countries.len() > MAX_PROFILE_COUNTRIES {
+        msg!("A profile allows at most {} countries", MAX_PROFILE_COUNTRIES);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if args.lockup_duration < 0 {
+        msg!("Lockup duration can't be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(ComplianceProfile {
+        key: Key::ComplianceProfileV1,
+        authority,
+        id: args.id,
+        max_holders: args.max_holders,
+        accredited_only: args.accredited_only,
+        lockup_duration: args.lockup_duration,
+        allowed_countries: args.allowed_countries,
+        transfer_restriction: args.transfer_restriction,
+        bump,
+    })
+}
+
+/// Load the Compliance Profile a vault follows, or None if it follows none. The vault compliance
+/// account is derived so a caller can't skip the profile by passing another account.
+fn load_compliance_profile(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    vault_compliance_info: &AccountInfo,
+    profile_info: &AccountInfo,
+) -> Result<Option<(VaultCompliance, ComplianceProfile)>, ProgramError> {
+    assert_derivation(
+        program_id,
+        vault_compliance_info,
+        &[VAULT_COMPLIANCE_PREFIX.as_bytes(), vault.as_ref()],
+    )?;
+    if vault_compliance_info.data_is_empty() {
+        return Ok(None);
+    }
+
+    assert_owned_by(vault_compliance_info, program_id)?;
+    let compliance = VaultCompliance::from_account_info(vault_compliance_info)?;
+    if compliance.profile != *profile_info.key {
+        msg!("Vault {} follows compliance profile {}", vault, compliance.profile);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_owned_by(profile_info, program_id)?;
+    let profile = ComplianceProfile::from_account_info(profile_info)?;
+
+    Ok(Some((compliance, profile)))
+}
+
+/// Set up the sanctions deny list, once, naming the compliance authority that manages it.
+/// Only the program's upgrade authority can do this.
+pub fn create_deny_list(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let upgrade_authority = next_account_info(accounts_iter)?;
+
+    let program_data = next_account_info(accounts_iter)?;
+
+    let deny_list_info = next_account_info(accounts_iter)?;
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_upgrade_authority(program_id, program_data, upgrade_authority)?;
+
+    let deny_list_bump = assert_derivation(program_id, deny_list_info, &[DENY_LIST_PREFIX.as_bytes()])?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        deny_list_info,
+        system_program,
+        rent,
+        DENY_LIST_SIZE,
+        &[DENY_LIST_PREFIX.as_bytes(), &[deny_list_bump]],
+    )?;
+
+    let deny_list = DenyList {
+        key: Key::DenyListV1,
+        compliance_authority: *compliance_authority.key,
+        bump: deny_list_bump,
+    };
+    deny_list.serialize(&mut &mut deny_list_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Block a wallet from receiving or sending shares and rNFTs through Tokrizer, effective immediately
+pub fn deny_wallet(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let deny_list_info = next_account_info(accounts_iter)?;
+
+    let denied_info = next_account_info(accounts_iter)?;
+
+    let wallet = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let compliance_role = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, compliance_role, compliance_authority.key, Role::Compliance)?;
+
+    load_deny_list(program_id, deny_list_info, compliance_authority)?;
+
+    let denied_bump = assert_derivation(
+        program_id,
+        denied_info,
+        &[DENIED_WALLET_PREFIX.as_bytes(), wallet.key.as_ref()],
+    )?;
+
+    if !denied_info.data_is_empty() {
+        msg!("{} is already denied", wallet.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        compliance_authority,
+        denied_info,
+        system_program,
+        rent,
+        DENIED_WALLET_SIZE,
+        &[DENIED_WALLET_PREFIX.as_bytes(), wallet.key.as_ref(), &[denied_bump]],
+    )?;
+
+    let denied = DeniedWallet {
+        key: Key::DeniedWalletV1,
+        wallet: *wallet.key,
+        denied_ts: Clock::get()?.unix_timestamp,
+    };
+    denied.serialize(&mut &mut denied_info.data.borrow_mut()[..])?;
+
+    msg!("{} denied", wallet.key);
+
+    Ok(())
+}
+
+/// Take a wallet off the deny list
+pub fn undeny_wallet(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let deny_list_info = next_account_info(accounts_iter)?;
+
+    let denied_info = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let compliance_role = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, compliance_role, compliance_authority.key, Role::Compliance)?;
+
+    load_deny_list(program_id, deny_list_info, compliance_authority)?;
+
+    assert_owned_by(denied_info, program_id)?;
+    let denied = DeniedWallet::from_account_info(denied_info)?;
+
+    close_program_account(denied_info, compliance_authority)?;
+
+    msg!("{} no longer denied", denied.wallet);
+
+    Ok(())
+}
+
+fn load_deny_list(
+    program_id: &Pubkey,
+    deny_list_info: &AccountInfo,
+    compliance_authority: &AccountInfo,
+) -> Result<DenyList, ProgramError> {
+    assert_owned_by(deny_list_info, program_id)?;
+    assert_derivation(program_id, deny_list_info, &[DENY_LIST_PREFIX.as_bytes()])?;
+    let deny_list = DenyList::from_account_info(deny_list_info)?;
+
+    if !compliance_authority.is_signer || deny_list.compliance_authority != *compliance_authority.key {
+        msg!("Deny list compliance authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    Ok(deny_list)
+}
+
+/// Fail with `WalletDenied` if the wallet is on the deny list. The denied wallet account is derived
+/// so a caller can't skip the check by passing another account.
+fn assert_not_denied(program_id: &Pubkey, wallet: &Pubkey, denied_info: &AccountInfo) -> ProgramResult {
+    assert_derivation(program_id, denied_info, &[DENIED_WALLET_PREFIX.as_bytes(), wallet.as_ref()])?;
+    if !denied_info.data_is_empty() {
+        msg!("{} is on the deny list", wallet);
+        return Err(TokrizerError::WalletDenied.into());
+    }
+
+    Ok(())
+}
+
+/// Fail unless the share account holds this mint and belongs to the wallet the compliance checks ran on,
+/// so shares can't be routed to an account owned by someone else
+fn assert_share_owner(share_account: &AccountInfo, wallet: &Pubkey, fraction_mint: &Pubkey) -> ProgramResult {
+    let account = Account::unpack(&share_account.data.borrow())?;
+    if account.owner != *wallet || account.mint != *fraction_mint {
+        msg!("Share account {} does not hold these shares for {}", share_account.key, wallet);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+/// Move shares or an rNFT out of a holder's account without their signature, e.g. under a court order.
+/// Both the deny list compliance authority and the program's upgrade authority must sign, and the
+/// holder's account must be delegated to the clawback PDA of its mint (see `approve_clawback`).
+///
+/// Tokrizer holds no authority of its own over holder accounts: SPL Token has no permanent delegate, and
+/// the freeze authorities of fraction mints and rNFTs belong to the token vault and metadata programs.
+/// So a clawback only works against a holder who approved it and hasn't revoked the delegation since.
+pub fn force_transfer(program_id: &Pubkey, accounts: &[AccountInfo], args: ForceTransferArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let compliance_authority = next_account_info(accounts_iter)?;
+
+    let deny_list_info = next_account_info(accounts_iter)?;
+
+    let upgrade_authority = next_account_info(accounts_iter)?;
+
+    let program_data = next_account_info(accounts_iter)?;
+
+    let source = next_account_info(accounts_iter)?;
+
+    let destination = next_account_info(accounts_iter)?;
+
+    let destination_ata = next_account_info(accounts_iter)?;
+
+    let destination_denied = next_account_info(accounts_iter)?;
+
+    let mint = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let portfolio_info = next_account_info(accounts_iter)?;
+
+    let clawback_authority = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let compliance_role = next_account_info(accounts_iter)?;
+
+    assert_role(program_id, config_info, compliance_role, compliance_authority.key, Role::Compliance)?;
+
+    load_deny_list(program_id, deny_list_info, compliance_authority)?;
+    assert_upgrade_authority(program_id, program_data, upgrade_authority)?;
+
+    // The mint is the vault's fraction mint or one of its rNFTs, so a paused vault holds back its clawbacks too
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+    let vault = load_vault(vault_info)?;
+    if vault.fraction_mint != *mint.key {
+        assert_owned_by(portfolio_info, program_id)?;
+        assert_derivation(
+            program_id,
+            portfolio_info,
+            &[PORTFOLIO_PREFIX.as_bytes(), vault_info.key.as_ref()],
+        )?;
+        if !Portfolio::from_account_info(portfolio_info)?.mints.contains(mint.key) {
+            msg!("{} is neither the fraction mint nor an rNFT of this vault", mint.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    assert_not_denied(program_id, destination.key, destination_denied)?;
+
+    let source_owner = Account::unpack(&source.data.borrow())?.owner;
+
+    if Account::unpack(&destination_ata.data.borrow()).is_err() {
+        invoke(
+            &create_associated_token_account(compliance_authority.key, destination.key, mint.key),
+            &[
+                compliance_authority.clone(),
+                destination_ata.clone(),
+                destination.clone(),
+                mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_program.clone(),
+            ],
+        )?;
+    }
+
+    assert_share_owner(destination_ata, destination.key, mint.key)?;
+
+    let source_before = share_balance(source);
+    let destination_before = share_balance(destination_ata);
+    clawback_transfer(program_id, accounts, source, destination_ata, mint, clawback_authority, args.amount)?;
+
+    // A court order isn't held back by the holder limit
+    update_holder_count(program_id, holder_registry, mint, source, source_before, false, None)?;
+    update_holder_count(program_id, holder_registry, mint, destination_ata, destination_before, false, None)?;
+
+    msg!(
+        "Forced transfer of {} {} from {} to {}, reason hash {}",
+        args.amount,
+        mint.key,
+        source_owner,
+        destination.key,
+        Hash::new_from_array(args.reason_hash)
+    );
+
+    Ok(())
+}
+
+/// Move `amount` out of a token account delegated to the clawback PDA of its mint
+fn clawback_transfer<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    source: &AccountInfo<'a>,
+    destination_ata: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    clawback_authority: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let clawback_bump = assert_derivation(
+        program_id,
+        clawback_authority,
+        &[CLAWBACK_PREFIX.as_bytes(), mint.key.as_ref()],
+    )?;
+
+    let source_account = Account::unpack(&source.data.borrow())?;
+    if source_account.mint != *mint.key {
+        msg!("Source account does not hold this mint");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if source_account.delegate != COption::Some(*clawback_authority.key)
+        || source_account.delegated_amount < amount
+    {
+        msg!("Source account has not delegated {} to the clawback authority", amount);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke_signed(
+        &transfer(&spl_token::id(), source.key, destination_ata.key, clawback_authority.key, &[], amount)?,
+        accounts,
+        &[&[CLAWBACK_PREFIX.as_bytes(), mint.key.as_ref(), &[clawback_bump]]],
+    )
+}
+
+/// Delegate a token account's shares or rNFT to the clawback PDA of its mint so `force_transfer`
+/// can move them. The holder can revoke the delegation or approve someone else at any time, which
+/// takes the account out of reach of a clawback.
+pub fn approve_clawback(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let owner = next_account_info(accounts_iter)?;
+
+    let token_account = next_account_info(accounts_iter)?;
+
+    let mint = next_account_info(accounts_iter)?;
+
+    let clawback_authority = next_account_info(accounts_iter)?;
+
+    let _token_program = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !owner.is_signer {
+        msg!("Owner must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    assert_derivation(
+        program_id,
+        clawback_authority,
+        &[CLAWBACK_PREFIX.as_bytes(), mint.key.as_ref()],
+    )?;
+
+    let account = Account::unpack(&token_account.data.borrow())?;
+    if account.mint != *mint.key || account.owner != *owner.key {
+        msg!("Token account is not the owner's account for this mint");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke(
+        &approve(
+            &spl_token::id(),
+            token_account.key,
+            clawback_authority.key,
+            owner.key,
+            &[],
+            u64::MAX,
+        )?,
+        accounts,
+    )?;
+
+    Ok(())
+}
+
+/// Register, or replace, the recovery key that can move the holder's shares to a new wallet
+/// if they lose this one. Can't be changed while a recovery is requested.
+///
+/// Recovery moves shares through the clawback PDA, so it only reaches share accounts the holder
+/// delegated with `approve_clawback` while they still had their wallet, and hasn't revoked since.
+pub fn set_recovery_key(program_id: &Pubkey, accounts: &[AccountInfo], delay: i64) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let holder = next_account_info(accounts_iter)?;
+
+    let recovery_info = next_account_info(accounts_iter)?;
+
+    let recovery_key = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    if !holder.is_signer {
+        msg!("Holder must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if delay < MIN_RECOVERY_DELAY {
+        msg!("Recovery delay must be at least {} seconds", MIN_RECOVERY_DELAY);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let recovery_bump = assert_derivation(
+        program_id,
+        recovery_info,
+        &[RECOVERY_PREFIX.as_bytes(), holder.key.as_ref()],
+    )?;
+
+    if recovery_info.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        create_program_account(
+            program_id,
+            holder,
+            recovery_info,
+            system_program,
+            rent,
+            RECOVERY_SIZE,
+            &[RECOVERY_PREFIX.as_bytes(), holder.key.as_ref(), &[recovery_bump]],
+        )?;
+    } else {
+        assert_owned_by(recovery_info, program_id)?;
+        if Recovery::from_account_info(recovery_info)?.is_requested() {
+            msg!("Cancel the requested recovery before changing the recovery key");
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    let recovery = Recovery {
+        key: Key::RecoveryV1,
+        holder: *holder.key,
+        recovery_key: *recovery_key.key,
+        delay,
+        new_wallet: Pubkey::default(),
+        requested_ts: 0,
+        bump: recovery_bump,
+    };
+    recovery.serialize(&mut &mut recovery_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Start the recovery of a holder's shares to a new wallet. It can be executed once the holder's
+/// delay has passed, and until then the holder or recovery key can cancel it.
+pub fn request_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recovery_key = next_account_info(accounts_iter)?;
+
+    let recovery_info = next_account_info(accounts_iter)?;
+
+    let new_wallet = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    let mut recovery = load_recovery(program_id, recovery_info, recovery_key)?;
+    if recovery.is_requested() {
+        msg!("A recovery to {} is already requested", recovery.new_wallet);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if *new_wallet.key == Pubkey::default() || *new_wallet.key == recovery.holder {
+        msg!("Recovery must be to a new wallet");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    recovery.new_wallet = *new_wallet.key;
+    recovery.requested_ts = Clock::get()?.unix_timestamp;
+    recovery.serialize(&mut &mut recovery_info.data.borrow_mut()[..])?;
+
+    msg!(
+        "Recovery of {} to {} can be executed from {}",
+        recovery.holder,
+        recovery.new_wallet,
+        recovery.requested_ts.saturating_add(recovery.delay)
+    );
+
+    Ok(())
+}
+
+/// Cancel a requested recovery, signed by either the holder or the recovery key
+pub fn cancel_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let signer = next_account_info(accounts_iter)?;
+
+    let recovery_info = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_owned_by(recovery_info, program_id)?;
+    let mut recovery = Recovery::from_account_info(recovery_info)?;
+
+    if !signer.is_signer || (*signer.key != recovery.holder && *signer.key != recovery.recovery_key) {
+        msg!("Holder or recovery key must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !recovery.is_requested() {
+        msg!("No recovery is requested");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    recovery.new_wallet = Pubkey::default();
+    recovery.requested_ts = 0;
+    recovery.serialize(&mut &mut recovery_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Move all of the holder's shares of a vault to the new wallet of a recovery whose delay has
+/// passed, through the clawback PDA the holder's share account is delegated to. Run once per
+/// vault the holder has shares in.
+pub fn execute_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let recovery_key = next_account_info(accounts_iter)?;
+
+    let recovery_info = next_account_info(accounts_iter)?;
+
+    let holder_share_account = next_account_info(accounts_iter)?;
+
+    let new_wallet = next_account_info(accounts_iter)?;
+
+    let new_wallet_ata = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let fraction_mint = next_account_info(accounts_iter)?;
+
+    let allowlist = next_account_info(accounts_iter)?;
+
+    let new_wallet_entry = next_account_info(accounts_iter)?;
+
+    let clawback_authority = next_account_info(accounts_iter)?;
+
+    let holder_registry = next_account_info(accounts_iter)?;
+
+    let vault_compliance = next_account_info(accounts_iter)?;
+
+    let compliance_profile = next_account_info(accounts_iter)?;
+
+    let new_wallet_denied = next_account_info(accounts_iter)?;
+
+    let token_program = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let _ata_program = next_account_info(accounts_iter)?;
+
+    let vault_pause = next_account_info(accounts_iter)?;
+
+    let _config = next_account_info(accounts_iter)?;
+
+    assert_vault_not_paused(program_id, vault_info.key, vault_pause)?;
+
+    let recovery = load_recovery(program_id, recovery_info, recovery_key)?;
+    if !recovery.is_requested() || recovery.new_wallet != *new_wallet.key {
+        msg!("No recovery to {} is requested", new_wallet.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let executable_ts = recovery.requested_ts.saturating_add(recovery.delay);
+    if Clock::get()?.unix_timestamp < executable_ts {
+        msg!("Recovery can't be executed until {}", executable_ts);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.fraction_mint != *fraction_mint.key {
+        msg!("Fraction mint does not belong to this vault");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let holder_account = Account::unpack(&holder_share_account.data.borrow())?;
+    if holder_account.owner != recovery.holder {
+        msg!("Share account does not belong to the holder being recovered");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    assert_not_denied(program_id, new_wallet.key, new_wallet_denied)?;
+
+    let profile = load_compliance_profile(program_id, vault_info.key, vault_compliance, compliance_profile)?
+        .map(|(_, profile)| profile);
+    assert_allowlisted(
+        program_id,
+        vault_info.key,
+        profile.as_ref(),
+        allowlist,
+        new_wallet_entry,
+        new_wallet.key,
+        None,
+    )?;
+
+    if Account::unpack(&new_wallet_ata.data.borrow()).is_err() {
+        invoke(
+            &create_associated_token_account(recovery_key.key, new_wallet.key, fraction_mint.key),
+            &[
+                recovery_key.clone(),
+                new_wallet_ata.clone(),
+                new_wallet.clone(),
+                fraction_mint.clone(),
+                system_program.clone(),
+                token_program.clone(),
+                rent_program.clone(),
+            ],
+        )?;
+    }
+    assert_share_owner(new_wallet_ata, new_wallet.key, fraction_mint.key)?;
+
+    let new_wallet_before = share_balance(new_wallet_ata);
+    clawback_transfer(
+        program_id,
+        accounts,
+        holder_share_account,
+        new_wallet_ata,
+        fraction_mint,
+        clawback_authority,
+        holder_account.amount,
+    )?;
+
+    // Count the lost wallet out first, so its place goes to the new wallet
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        holder_share_account,
+        holder_account.amount,
+        true,
+        profile.as_ref(),
+    )?;
+    update_holder_count(
+        program_id,
+        holder_registry,
+        fraction_mint,
+        new_wallet_ata,
+        new_wallet_before,
+        true,
+        profile.as_ref(),
+    )?;
+
+    msg!("Recovered {} shares of {} to {}", holder_account.amount, recovery.holder, new_wallet.key);
+
+    Ok(())
+}
+
+fn load_recovery(
+    program_id: &Pubkey,
+    recovery_info: &AccountInfo,
+    recovery_key: &AccountInfo,
+) -> Result<Recovery, ProgramError> {
+    assert_owned_by(recovery_info, program_id)?;
+    let recovery = Recovery::from_account_info(recovery_info)?;
+
+    if !recovery_key.is_signer || recovery.recovery_key != *recovery_key.key {
+        msg!("Recovery key must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    Ok(recovery)
+}
+
+/// Create the program's Config, once. Only the program's upgrade authority can do this, after
+/// which the config's admin manages it.
+pub fn initialize_config(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let upgrade_authority = next_account_info(accounts_iter)?;
+
+    let program_data = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let treasury = next_account_info(accounts_iter)?;
+
+    assert_upgrade_authority(program_id, program_data, upgrade_authority)?;
+
+    let config_bump = assert_derivation(program_id, config_info, &[CONFIG_PREFIX.as_bytes()])?;
+
+    assert_derivation(program_id, treasury, &[TREASURY_PREFIX.as_bytes()])?;
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        payer,
+        config_info,
+        system_program,
+        rent,
+        CONFIG_SIZE,
+        &[CONFIG_PREFIX.as_bytes(), &[config_bump]],
+    )?;
+
+    let config = config_from_args(args, config_bump)?;
+    config.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+
+    // The treasury is a data-less system account, so fund it up to rent exemption here. Otherwise
+    // the first fee into it would have to cover that by itself.
+    let treasury_shortfall = rent.minimum_balance(0).saturating_sub(treasury.lamports());
+    if treasury_shortfall > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, treasury.key, treasury_shortfall),
+            &[payer.clone(), treasury.clone(), system_program.clone()],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Replace the program's Config settings, signed by its admin. Handing over to a new admin is done
+/// by setting `admin`.
+pub fn update_config(program_id: &Pubkey, accounts: &[AccountInfo], args: ConfigArgs) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let config = load_config_as_admin(program_id, config_info, admin)?;
+
+    let mut updated = config_from_args(args, config.bump)?;
+    updated.paused = config.paused;
+    updated.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn config_from_args(args: ConfigArgs, bump: u8) -> Result<Config, ProgramError> {
+    if args.sale_fee_bps > MAX_FEE_BPS {
+        msg!("Sale fee can't be more than {} basis points", MAX_FEE_BPS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(Config {
+        key: Key::ConfigV1,
+        admin: args.admin,
+        treasury: args.treasury,
+        mint_fee: args.mint_fee,
+        vault_fee: args.vault_fee,
+        sale_fee_bps: args.sale_fee_bps,
+        metadata_program: args.metadata_program,
+        vault_program: args.vault_program,
+        paused: false,
+        bump,
+    })
+}
+
+/// The program's Config, or None before it is initialized. The account is derived so a caller
+/// can't pass another one.
+fn load_config(program_id: &Pubkey, config_info: &AccountInfo) -> Result<Option<Config>, ProgramError> {
+    assert_derivation(program_id, config_info, &[CONFIG_PREFIX.as_bytes()])?;
+    if config_info.data_is_empty() {
+        return Ok(None);
+    }
+
+    assert_owned_by(config_info, program_id)?;
+    Ok(Some(Config::from_account_info(config_info)?))
+}
+
+fn load_config_as_admin(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    admin: &AccountInfo,
+) -> Result<Config, ProgramError> {
+    let config = load_config(program_id, config_info)?.ok_or(ProgramError::UninitializedAccount)?;
+
+    if !admin.is_signer || config.admin != *admin.key {
+        msg!("Config admin must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    Ok(config)
+}
+
+/// Give a wallet a role, signed by the config admin or a holder of the Admin role
+pub fn grant_role(program_id: &Pubkey, accounts: &[AccountInfo], role: Role) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let admin_role = next_account_info(accounts_iter)?;
+
+    let role_info = next_account_info(accounts_iter)?;
+
+    let wallet = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    load_config_with_role(program_id, config_info, admin_role, admin, Role::Admin)?;
+
+    let role_bump = assert_derivation(
+        program_id,
+        role_info,
+        &[ROLE_PREFIX.as_bytes(), wallet.key.as_ref(), &[role as u8]],
+    )?;
+
+    if !role_info.data_is_empty() {
+        msg!("{} already has the {:?} role", wallet.key, role);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    create_program_account(
+        program_id,
+        admin,
+        role_info,
+        system_program,
+        rent,
+        ROLE_SIZE,
+        &[ROLE_PREFIX.as_bytes(), wallet.key.as_ref(), &[role as u8], &[role_bump]],
+    )?;
+
+    let assignment = RoleAssignment {
+        key: Key::RoleV1,
+        wallet: *wallet.key,
+        role,
+        granted_by: *admin.key,
+        granted_ts: Clock::get()?.unix_timestamp,
+    };
+    assignment.serialize(&mut &mut role_info.data.borrow_mut()[..])?;
+
+    msg!("{} granted the {:?} role", wallet.key, role);
+
+    Ok(())
+}
+
+/// Take a role away from a wallet, signed by the config admin or a holder of the Admin role
+pub fn revoke_role(program_id: &Pubkey, accounts: &[AccountInfo], role: Role) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let admin_role = next_account_info(accounts_iter)?;
+
+    let role_info = next_account_info(accounts_iter)?;
+
+    let wallet = next_account_info(accounts_iter)?;
+
+    load_config_with_role(program_id, config_info, admin_role, admin, Role::Admin)?;
+
+    assert_derivation(
+        program_id,
+        role_info,
+        &[ROLE_PREFIX.as_bytes(), wallet.key.as_ref(), &[role as u8]],
+    )?;
+    assert_owned_by(role_info, program_id)?;
+    RoleAssignment::from_account_info(role_info)?;
+
+    close_program_account(role_info, admin)?;
+
+    msg!("{} no longer has the {:?} role", wallet.key, role);
+
+    Ok(())
+}
+
+/// Load the config, failing unless the config admin or a holder of the role has signed
+fn load_config_with_role(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    role_info: &AccountInfo,
+    signer: &AccountInfo,
+    role: Role,
+) -> Result<Config, ProgramError> {
+    let config = load_config(program_id, config_info)?.ok_or_else(|| {
+        msg!("Config is not initialized");
+        ProgramError::UninitializedAccount
+    })?;
+
+    if !signer.is_signer {
+        msg!("{:?} must sign", role);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if config.admin != *signer.key {
+        assert_has_role(program_id, role_info, signer.key, role)?;
+    }
+
+    Ok(config)
+}
+
+/// Fail with `MissingRole` unless the wallet holds the role. Roles are granted under the config, so
+/// nothing that requires one can run until the config is initialized.
+fn assert_role(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    role_info: &AccountInfo,
+    wallet: &Pubkey,
+    role: Role,
+) -> ProgramResult {
+    if load_config(program_id, config_info)?.is_none() {
+        msg!("Config is not initialized, so no one holds the {:?} role yet", role);
+        return Err(TokrizerError::MissingRole.into());
+    }
+
+    assert_has_role(program_id, role_info, wallet, role)
+}
+
+fn assert_has_role(
+    program_id: &Pubkey,
+    role_info: &AccountInfo,
+    wallet: &Pubkey,
+    role: Role,
+) -> ProgramResult {
+    assert_derivation(program_id, role_info, &[ROLE_PREFIX.as_bytes(), wallet.as_ref(), &[role as u8]])?;
+    if role_info.data_is_empty() {
+        msg!("{} does not have the {:?} role", wallet, role);
+        return Err(TokrizerError::MissingRole.into());
+    }
+
+    assert_owned_by(role_info, program_id)?;
+    let assignment = RoleAssignment::from_account_info(role_info)?;
+    if assignment.wallet != *wallet || assignment.role != role {
+        msg!("Role account does not give {} the {:?} role", wallet, role);
+        return Err(TokrizerError::MissingRole.into());
+    }
+
+    Ok(())
+}
+
+/// Send the protocol fees collected in the treasury to the config's treasury wallet, signed by
+/// the admin: the lamports above the treasury's rent exemption and, if a treasury token account is
+/// given, all of its tokens to the treasury wallet's token account of that mint
+pub fn withdraw_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let admin = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let treasury = next_account_info(accounts_iter)?;
+
+    let treasury_wallet = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    // Optional treasury token account to empty, and where its tokens go
+    let fee_account = next_account_info(accounts_iter).ok();
+
+    let fee_destination = next_account_info(accounts_iter).ok();
+
+    let _token_program = next_account_info(accounts_iter).ok();
+
+    let config = load_config_as_admin(program_id, config_info, admin)?;
+
+    if config.treasury != *treasury_wallet.key {
+        msg!("Fees can only be withdrawn to the config's treasury {}", config.treasury);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let treasury_bump = assert_derivation(program_id, treasury, &[TREASURY_PREFIX.as_bytes()])?;
+    let treasury_signer_seeds: &[&[u8]] = &[TREASURY_PREFIX.as_bytes(), &[treasury_bump]];
+
+    let rent = &Rent::from_account_info(rent_program)?;
+    let lamports = treasury.lamports().saturating_sub(rent.minimum_balance(0));
+    if lamports > 0 {
+        invoke_signed(
+            &system_instruction::transfer(treasury.key, treasury_wallet.key, lamports),
+            &[treasury.clone(), treasury_wallet.clone(), system_program.clone()],
+            &[treasury_signer_seeds],
+        )?;
+    }
+
+    if let (Some(fee_account), Some(fee_destination)) = (fee_account, fee_destination) {
+        let fees = Account::unpack(&fee_account.data.borrow())?;
+        let destination = Account::unpack(&fee_destination.data.borrow())?;
+        if destination.owner != config.treasury || destination.mint != fees.mint {
+            msg!("Fees must go to a {} account of the treasury", fees.mint);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if fees.amount > 0 {
+            invoke_signed(
+                &transfer(
+                    &spl_token::id(),
+                    fee_account.key,
+                    fee_destination.key,
+                    treasury.key,
+                    &[],
+                    fees.amount,
+                )?,
+                accounts,
+                &[treasury_signer_seeds],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Take a flat fee in lamports from the payer into the treasury
+fn charge_flat_fee<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    treasury: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    fee: u64,
+) -> ProgramResult {
+    assert_derivation(program_id, treasury, &[TREASURY_PREFIX.as_bytes()])?;
+    if fee == 0 {
+        return Ok(());
+    }
+
+    invoke(
+        &system_instruction::transfer(payer.key, treasury.key, fee),
+        &[payer.clone(), treasury.clone(), system_program.clone()],
+    )
+}
+
+/// The config's sale fee on an amount, 0 before the config is initialized
+fn sale_fee(config: Option<&Config>, amount: u64) -> u64 {
+    config.map_or(0, |config| {
+        (amount as u128 * config.sale_fee_bps as u128 / MAX_FEE_BPS as u128) as u64
+    })
+}
+
+/// Check the account is the treasury's token account for the mint, for sale fees paid in it
+fn assert_fee_account(program_id: &Pubkey, fee_account: &AccountInfo, mint: &Pubkey) -> ProgramResult {
+    let (treasury, _) = Pubkey::find_program_address(&[TREASURY_PREFIX.as_bytes()], program_id);
+    let account = Account::unpack(&fee_account.data.borrow())?;
+    if account.owner != treasury || account.mint != *mint {
+        msg!("Fee account must be a {} account of the treasury {}", mint, treasury);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+/// Pay an offering's proceeds out to its destination, less the sale fee which goes to the treasury
+#[allow(clippy::too_many_arguments)]
+fn pay_out_proceeds<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    config_info: &AccountInfo<'a>,
+    fee_account: &AccountInfo<'a>,
+    proceeds: &AccountInfo<'a>,
+    proceeds_destination: &AccountInfo<'a>,
+    offering_info: &AccountInfo<'a>,
+    offering: &Offering,
+) -> ProgramResult {
+    let offering_signer_seeds: &[&[u8]] = &[OFFERING_PREFIX.as_bytes(), offering.vault.as_ref(), &[offering.bump]];
+
+    let raised = Account::unpack(&proceeds.data.borrow())?.amount;
+    let fee = sale_fee(load_config(program_id, config_info)?.as_ref(), raised);
+    if fee > 0 {
+        assert_fee_account(program_id, fee_account, &offering.payment_mint)?;
+        invoke_signed(
+            &transfer(&spl_token::id(), proceeds.key, fee_account.key, offering_info.key, &[], fee)?,
+            accounts,
+            &[offering_signer_seeds],
+        )?;
+    }
+
+    if raised > fee {
+        invoke_signed(
+            &transfer(
+                &spl_token::id(),
+                proceeds.key,
+                proceeds_destination.key,
+                offering_info.key,
+                &[],
+                raised - fee,
+            )?,
+            accounts,
+            &[offering_signer_seeds],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Pause or unpause every instruction that takes the config, but these switches, signed by the config
+/// admin or a holder of the Emergency role
+pub fn set_paused(program_id: &Pubkey, accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let emergency_authority = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let emergency_role = next_account_info(accounts_iter)?;
+
+    let mut config =
+        load_config_with_role(program_id, config_info, emergency_role, emergency_authority, Role::Emergency)?;
+
+    config.paused = paused;
+    config.serialize(&mut &mut config_info.data.borrow_mut()[..])?;
+
+    msg!("Program {}", if paused { "paused" } else { "unpaused" });
+
+    Ok(())
+}
+
+/// Pause or unpause every instruction on one vault, signed by the config admin or a holder of the
+/// Emergency role. The vault's VaultPause account is created the first time it is paused.
+pub fn set_vault_paused(program_id: &Pubkey, accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let emergency_authority = next_account_info(accounts_iter)?;
+
+    let config_info = next_account_info(accounts_iter)?;
+
+    let emergency_role = next_account_info(accounts_iter)?;
+
+    let vault_info = next_account_info(accounts_iter)?;
+
+    let vault_pause_info = next_account_info(accounts_iter)?;
+
+    let system_program = next_account_info(accounts_iter)?;
+
+    let rent_program = next_account_info(accounts_iter)?;
+
+    let config =
+        load_config_with_role(program_id, config_info, emergency_role, emergency_authority, Role::Emergency)?;
+
+    assert_owned_by(vault_info, &config.vault_program)?;
+    Vault::from_account_info(vault_info)?;
+
+    let vault_pause_bump = assert_derivation(
+        program_id,
+        vault_pause_info,
+        &[VAULT_PAUSE_PREFIX.as_bytes(), vault_info.key.as_ref()],
+    )?;
+
+    let was_paused = if vault_pause_info.data_is_empty() {
+        false
+    } else {
+        assert_owned_by(vault_pause_info, program_id)?;
+        VaultPause::from_account_info(vault_pause_info)?.paused
+    };
+    if was_paused == paused {
+        msg!("Vault {} is already {}", vault_info.key, if paused { "paused" } else { "unpaused" });
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if vault_pause_info.data_is_empty() {
+        let rent = &Rent::from_account_info(rent_program)?;
+        create_program_account(
+            program_id,
+            emergency_authority,
+            vault_pause_info,
+            system_program,
+            rent,
+            VAULT_PAUSE_SIZE,
+            &[VAULT_PAUSE_PREFIX.as_bytes(), vault_info.key.as_ref(), &[vault_pause_bump]],
+        )?;
+    }
+
+    let vault_pause = VaultPause {
+        key: Key::VaultPauseV1,
+        vault: *vault_info.key,
+        paused,
+        bump: vault_pause_bump,
+    };
+    vault_pause.serialize(&mut &mut vault_pause_info.data.borrow_mut()[..])?;
+
+    msg!("Vault {} {}", vault_info.key, if paused { "paused" } else { "unpaused" });
+
+    Ok(())
+}
+
+/// Find the config account among an instruction's accounts, wherever the instruction takes it
+fn find_config<'a, 'b>(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let (config_key, _) = Pubkey::find_program_address(&[CONFIG_PREFIX.as_bytes()], program_id);
+    accounts.iter().find(|account| *account.key == config_key).ok_or_else(|| {
+        msg!("Config account {} is required", config_key);
+        ProgramError::NotEnoughAccountKeys
+    })
+}
+
+/// Fail while the program is paused. Nothing is paused before the config is initialized.
+fn assert_program_not_paused(program_id: &Pubkey, config_info: &AccountInfo) -> ProgramResult {
+    if load_config(program_id, config_info)?.map_or(false, |config| config.paused) {
+        msg!("Program is paused");
+        return Err(TokrizerError::ProgramPaused.into());
+    }
+
+    Ok(())
+}
+
+/// Fail unless the token vault program is the one the config allows, or Metaplex's before there is a config
+fn assert_vault_program(program_id: &Pubkey, config_info: &AccountInfo, token_vault_program: &AccountInfo) -> ProgramResult {
+    let vault_program =
+        load_config(program_id, config_info)?.map_or_else(mpl_token_vault::id, |config| config.vault_program);
+    if *token_vault_program.key != vault_program {
+        msg!("Token vault program is not the one allowed by the config");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    Ok(())
+}
+
+/// Fail while the vault is paused. A vault that was never paused has no VaultPause account yet.
+fn assert_vault_not_paused(program_id: &Pubkey, vault: &Pubkey, vault_pause_info: &AccountInfo) -> ProgramResult {
+    assert_derivation(program_id, vault_pause_info, &[VAULT_PAUSE_PREFIX.as_bytes(), vault.as_ref()])?;
+    if vault_pause_info.data_is_empty() {
+        return Ok(());
+    }
+
+    assert_owned_by(vault_pause_info, program_id)?;
+    if VaultPause::from_account_info(vault_pause_info)?.paused {
+        msg!("Vault {} is paused", vault);
+        return Err(TokrizerError::VaultPaused.into());
+    }
+
+    Ok(())
+}
+
+/// Load a vault, checking the token vault program owns it. Vault data is only trusted as far as its owner,
+/// so without this anyone could pass an account of their own naming a real fraction mint.
+fn load_vault(vault_info: &AccountInfo) -> Result<Vault, ProgramError> {
+    assert_owned_by(vault_info, &mpl_token_vault::id())?;
+    Vault::from_account_info(vault_info)
+}
+
+/// Load a vault whose authority has signed
+fn load_vault_as_authority(vault_info: &AccountInfo, vault_authority: &AccountInfo) -> Result<Vault, ProgramError> {
+    if !vault_authority.is_signer {
+        msg!("Vault authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let vault = load_vault(vault_info)?;
+    if vault.authority != *vault_authority.key {
+        msg!("Signer is not the vault authority");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(vault)
+}
+
 // I had to write this because mpl_token_vault::instruction::create_add_token_to_inactive_vault_instruction
 // does not work! PR: https://github.com/metaplex-foundation/metaplex-program-library/pull/310
 #[allow(clippy::too_many_arguments)]
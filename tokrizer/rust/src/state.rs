@@ -0,0 +1,1201 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, borsh::try_from_slice_unchecked, msg,
+    program_error::ProgramError, pubkey::Pubkey,
+};
+
+pub const PORTFOLIO_PREFIX: &str = "portfolio";
+
+// The most rNFTs a single vault can hold, bounded so the Portfolio account can be allocated up front
+pub const MAX_PORTFOLIO_MINTS: usize = 16;
+
+pub const MAX_PORTFOLIO_SIZE: usize = 1 + 32 + 32 + 4 + 32 * MAX_PORTFOLIO_MINTS;
+
+pub const OFFERING_PREFIX: &str = "offering";
+
+pub const OFFERING_SHARES_PREFIX: &str = "offering_shares";
+
+pub const PROCEEDS_PREFIX: &str = "proceeds";
+
+pub const PURCHASE_PREFIX: &str = "purchase";
+
+pub const OFFERING_NONCE_PREFIX: &str = "offering_nonce";
+
+pub const OFFERING_SIZE: usize = 1 + 32 * 5 + 8 * 6 + 1 + 8 + 1 + 8 + 8;
+
+pub const OFFERING_NONCE_SIZE: usize = 1 + 32 + 8;
+
+pub const PURCHASE_SIZE: usize = 1 + 32 + 32 + 8 + 8 + 8;
+
+pub const DISTRIBUTION_PREFIX: &str = "distribution";
+
+pub const INCOME_PREFIX: &str = "income";
+
+pub const STAKED_PREFIX: &str = "staked";
+
+pub const STAKE_PREFIX: &str = "stake";
+
+/// Fixed point scale of `Distribution::income_per_share`
+pub const INCOME_PER_SHARE_PRECISION: u128 = 1_000_000_000_000;
+
+pub const DISTRIBUTION_SIZE: usize = 1 + 32 * 4 + 8 + 16 + 1;
+
+pub const HOLDER_STAKE_SIZE: usize = 1 + 32 + 32 + 8 + 16;
+
+pub const SNAPSHOT_PREFIX: &str = "snapshot";
+
+pub const SNAPSHOT_PAYOUT_PREFIX: &str = "snapshot_payout";
+
+pub const SNAPSHOT_CLAIM_PREFIX: &str = "snapshot_claim";
+
+pub const SNAPSHOT_SIZE: usize = 1 + 32 + 32 + 8 + 8 + 32 + 8 + 32 + 8 + 1;
+
+pub const SNAPSHOT_CLAIM_SIZE: usize = 1 + 32 + 32 + 8;
+
+pub const GOVERNANCE_PREFIX: &str = "governance";
+
+pub const PROPOSAL_PREFIX: &str = "proposal";
+
+pub const VOTE_ESCROW_PREFIX: &str = "vote_escrow";
+
+pub const VOTE_PREFIX: &str = "vote";
+
+pub const GOVERNANCE_SIZE: usize = 1 + 32 * 3 + 2 + 2 + 8 + 8 + 1;
+
+pub const MAX_PROPOSAL_ACTION_SIZE: usize = 1 + 8 + 1;
+
+pub const PROPOSAL_SIZE: usize = 1 + 32 + 32 + 8 + MAX_PROPOSAL_ACTION_SIZE + 8 * 4 + 1 + 1 + 8;
+
+pub const VOTE_RECORD_SIZE: usize = 1 + 32 + 32 + 1 + 8;
+
+pub const ALLOWLIST_PREFIX: &str = "allowlist";
+
+pub const ALLOWLIST_ENTRY_PREFIX: &str = "allowlist_entry";
+
+pub const ALLOWLIST_SIZE: usize = 1 + 32 + 32 + 32 + 1;
+
+pub const ALLOWLIST_ENTRY_SIZE: usize = 1 + 32 + 32 + 8 + 1 + 2;
+
+pub const VESTING_PREFIX: &str = "vesting";
+
+pub const VESTING_SHARES_PREFIX: &str = "vesting_shares";
+
+pub const VESTING_SIZE: usize = 1 + 32 * 4 + 8 * 3 + 8 + 8 + 1;
+
+pub const HOLDER_REGISTRY_PREFIX: &str = "holders";
+
+pub const HOLDER_REGISTRY_SIZE: usize = 1 + 32 + 32 + 8 + 8 + 1;
+
+pub const COMPLIANCE_PROFILE_PREFIX: &str = "compliance_profile";
+
+pub const VAULT_COMPLIANCE_PREFIX: &str = "vault_compliance";
+
+pub const MAX_PROFILE_COUNTRIES: usize = 32;
+
+pub const COMPLIANCE_PROFILE_SIZE: usize = 1 + 32 + 8 + 8 + 1 + 8 + 4 + 2 * MAX_PROFILE_COUNTRIES + 1 + 1;
+
+pub const VAULT_COMPLIANCE_SIZE: usize = 1 + 32 + 32 + 8 + 1;
+
+pub const DENY_LIST_PREFIX: &str = "deny_list";
+
+pub const DENIED_WALLET_PREFIX: &str = "denied";
+
+pub const DENY_LIST_SIZE: usize = 1 + 32 + 1;
+
+pub const DENIED_WALLET_SIZE: usize = 1 + 32 + 8;
+
+/// Seed of the PDA, per mint, that holders delegate their shares or rNFT to so they can be force transferred
+pub const CLAWBACK_PREFIX: &str = "clawback";
+
+pub const RECOVERY_PREFIX: &str = "recovery";
+
+/// Shortest time a holder can give themselves to cancel a recovery of their wallet, 2 days
+pub const MIN_RECOVERY_DELAY: i64 = 2 * 24 * 60 * 60;
+
+pub const RECOVERY_SIZE: usize = 1 + 32 + 32 + 8 + 32 + 8 + 1;
+
+pub const CONFIG_PREFIX: &str = "config";
+
+pub const CONFIG_SIZE: usize = 1 + 32 + 32 + 8 + 8 + 2 + 32 + 32 + 1 + 1;
+
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+/// Seed of the PDA protocol fees are collected in, in lamports and in token accounts it owns
+pub const TREASURY_PREFIX: &str = "treasury";
+
+pub const ROLE_PREFIX: &str = "role";
+
+pub const ROLE_SIZE: usize = 1 + 32 + 1 + 32 + 8;
+
+pub const VAULT_PAUSE_PREFIX: &str = "vault_pause";
+
+pub const VAULT_PAUSE_SIZE: usize = 1 + 32 + 1 + 1;
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum Key {
+    Uninitialized,
+    PortfolioV1,
+    OfferingV1,
+    PurchaseV1,
+    DistributionV1,
+    HolderStakeV1,
+    SnapshotV1,
+    SnapshotClaimV1,
+    GovernanceV1,
+    ProposalV1,
+    VoteRecordV1,
+    AllowlistV1,
+    AllowlistEntryV1,
+    VestingV1,
+    HolderRegistryV1,
+    ComplianceProfileV1,
+    VaultComplianceV1,
+    DenyListV1,
+    DeniedWalletV1,
+    RecoveryV1,
+    ConfigV1,
+    RoleV1,
+    OfferingNonceV1,
+    VaultPauseV1,
+}
+
+/// The list of rNFT mints deposited into a vault, one safety deposit box each
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Portfolio {
+    pub key: Key,
+    pub vault: Pubkey,
+    /// Who paid the rent for the vault accounts and gets it back on CloseVault
+    pub payer: Pubkey,
+    pub mints: Vec<Pubkey>,
+}
+
+impl Portfolio {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Portfolio, ProgramError> {
+        let portfolio: Portfolio = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if portfolio.key != Key::PortfolioV1 {
+            msg!("Portfolio account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(portfolio)
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum OfferingState {
+    Open,
+    /// The minimum raise was met, buyers can claim their shares
+    Succeeded,
+    /// The minimum raise was missed, buyers can get refunds
+    Failed,
+}
+
+/// A primary sale of a vault's shares at a fixed price. The shares for sale are moved out of the
+/// fraction treasury into an escrow owned by the offering when it opens.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Offering {
+    pub key: Key,
+    pub vault: Pubkey,
+    /// The vault authority that opened the offering and receives the proceeds
+    pub authority: Pubkey,
+    /// Who paid the rent for the offering accounts
+    pub payer: Pubkey,
+    pub fraction_mint: Pubkey,
+    pub payment_mint: Pubkey,
+    /// Price in payment mint base units for one fraction mint base unit
+    pub price_per_share: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    /// Zero means no cap
+    pub max_shares_per_wallet: u64,
+    pub shares_offered: u64,
+    pub shares_sold: u64,
+    pub bump: u8,
+    /// All-or-nothing raises hold payments and shares in escrow until finalized. Zero means
+    /// there is no minimum and buyers get their shares immediately.
+    pub min_shares: u64,
+    pub state: OfferingState,
+    /// Escrowed purchases not yet claimed or refunded
+    pub open_purchases: u64,
+    /// Which of the vault's offerings this is, see OfferingNonce
+    pub nonce: u64,
+}
+
+impl Offering {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Offering, ProgramError> {
+        let offering: Offering = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if offering.key != Key::OfferingV1 {
+            msg!("Offering account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(offering)
+    }
+}
+
+/// How many shares a wallet has bought in an offering, used to enforce the per wallet cap
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Purchase {
+    pub key: Key,
+    pub offering: Pubkey,
+    pub buyer: Pubkey,
+    /// Nonce of the offering this was recorded in, an offering PDA is reused when a vault opens a new one
+    pub offering_nonce: u64,
+    pub shares_purchased: u64,
+    /// Payment held in the proceeds account for an escrowed purchase
+    pub amount_paid: u64,
+}
+
+/// Counts the offerings a vault has opened. It outlives each Offering account, so every offering gets
+/// a nonce of its own.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct OfferingNonce {
+    pub key: Key,
+    pub vault: Pubkey,
+    pub nonce: u64,
+}
+
+impl OfferingNonce {
+    pub fn from_account_info(a: &AccountInfo) -> Result<OfferingNonce, ProgramError> {
+        let offering_nonce: OfferingNonce = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if offering_nonce.key != Key::OfferingNonceV1 {
+            msg!("Offering nonce account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(offering_nonce)
+    }
+}
+
+impl Purchase {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Purchase, ProgramError> {
+        let purchase: Purchase = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if purchase.key != Key::PurchaseV1 {
+            msg!("Purchase account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(purchase)
+    }
+}
+
+/// Rental income paid out to holders of a vault's shares. Holders stake their shares with the
+/// distribution so every balance change goes through this program, which keeps a running total of
+/// income per staked share. A holder is owed `amount * income_per_share - income_debt`.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Distribution {
+    pub key: Key,
+    pub vault: Pubkey,
+    pub fraction_mint: Pubkey,
+    /// The token income is paid in, e.g. USDC
+    pub payment_mint: Pubkey,
+    /// The property manager allowed to deposit income
+    pub manager: Pubkey,
+    pub total_staked: u64,
+    /// Cumulative income per staked share, scaled by INCOME_PER_SHARE_PRECISION
+    pub income_per_share: u128,
+    pub bump: u8,
+}
+
+impl Distribution {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Distribution, ProgramError> {
+        let distribution: Distribution = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if distribution.key != Key::DistributionV1 {
+            msg!("Distribution account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(distribution)
+    }
+
+    /// Credit `amount` of income pro-rata to every staked share
+    pub fn credit_income(&mut self, amount: u64) -> Result<(), ProgramError> {
+        if self.total_staked == 0 {
+            msg!("No shares are staked to receive income");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        self.income_per_share = (amount as u128)
+            .checked_mul(INCOME_PER_SHARE_PRECISION)
+            .map(|scaled| scaled / self.total_staked as u128)
+            .and_then(|increase| self.income_per_share.checked_add(increase))
+            .ok_or(ProgramError::InvalidArgument)?;
+
+        Ok(())
+    }
+}
+
+/// A holder's shares staked with a distribution
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct HolderStake {
+    pub key: Key,
+    pub distribution: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    /// `amount * income_per_share` when the holder was last paid, scaled by INCOME_PER_SHARE_PRECISION
+    pub income_debt: u128,
+}
+
+impl HolderStake {
+    pub fn from_account_info(a: &AccountInfo) -> Result<HolderStake, ProgramError> {
+        let stake: HolderStake = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if stake.key != Key::HolderStakeV1 {
+            msg!("Holder stake account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(stake)
+    }
+
+    /// Income owed since the holder was last paid, scaled by INCOME_PER_SHARE_PRECISION
+    pub fn pending_income(&self, income_per_share: u128) -> Result<u128, ProgramError> {
+        (self.amount as u128)
+            .checked_mul(income_per_share)
+            .and_then(|earned| earned.checked_sub(self.income_debt))
+            .ok_or(ProgramError::InvalidArgument)
+    }
+
+    /// Change the staked amount once `pending` has been paid out. The debt is rounded down by what was left
+    /// unpaid, so fractions of a token carry over to the next claim.
+    pub fn restake(&mut self, amount: u64, income_per_share: u128, pending: u128) -> Result<(), ProgramError> {
+        self.amount = amount;
+        self.income_debt = (amount as u128)
+            .checked_mul(income_per_share)
+            .ok_or(ProgramError::InvalidArgument)?
+            .saturating_sub(pending % INCOME_PER_SHARE_PRECISION);
+
+        Ok(())
+    }
+}
+
+/// A frozen view of who held how many of a vault's shares at a record date, committed as the Merkle
+/// root of (holder, balance) leaves built off chain. Can optionally be funded to pay holders pro-rata.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Snapshot {
+    pub key: Key,
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub id: u64,
+    /// The record date the balances were read at
+    pub slot: u64,
+    pub merkle_root: [u8; 32],
+    /// Sum of every balance in the tree
+    pub total_shares: u64,
+    /// Default until the snapshot is first funded
+    pub payment_mint: Pubkey,
+    /// Total funded so far, holders are owed `balance * payout_amount / total_shares`
+    pub payout_amount: u64,
+    pub bump: u8,
+}
+
+impl Snapshot {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Snapshot, ProgramError> {
+        let snapshot: Snapshot = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if snapshot.key != Key::SnapshotV1 {
+            msg!("Snapshot account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// How much a holder has been paid from a snapshot
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct SnapshotClaim {
+    pub key: Key,
+    pub snapshot: Pubkey,
+    pub holder: Pubkey,
+    pub claimed: u64,
+}
+
+impl SnapshotClaim {
+    pub fn from_account_info(a: &AccountInfo) -> Result<SnapshotClaim, ProgramError> {
+        let claim: SnapshotClaim = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if claim.key != Key::SnapshotClaimV1 {
+            msg!("Snapshot claim account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(claim)
+    }
+}
+
+/// Shareholder governance settings for a vault
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Governance {
+    pub key: Key,
+    pub vault: Pubkey,
+    /// The vault authority that handed the vault to this governance
+    pub authority: Pubkey,
+    pub fraction_mint: Pubkey,
+    /// Share of the outstanding supply that must vote, in basis points
+    pub quorum_bps: u16,
+    /// Share of the votes cast that must approve, in basis points
+    pub threshold_bps: u16,
+    /// Seconds a proposal is open for voting
+    pub voting_period: i64,
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl Governance {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Governance, ProgramError> {
+        let governance: Governance = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if governance.key != Key::GovernanceV1 {
+            msg!("Governance account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(governance)
+    }
+}
+
+/// What a proposal does once it passes
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub enum ProposalAction {
+    /// Combine the vault, e.g. to sell the property to a buyer paying out the share holders
+    CombineVault,
+    /// Change the price shares are bought out at
+    UpdateExternalPrice {
+        price_per_share: u64,
+        allowed_to_combine: bool,
+    },
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum ProposalState {
+    Voting,
+    Succeeded,
+    Defeated,
+    Executed,
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Proposal {
+    pub key: Key,
+    pub governance: Pubkey,
+    pub proposer: Pubkey,
+    pub index: u64,
+    pub action: ProposalAction,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub state: ProposalState,
+    pub bump: u8,
+    /// Shares outstanding when the proposal was created, which quorum is measured against
+    pub quorum_base: u64,
+}
+
+impl Proposal {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Proposal, ProgramError> {
+        let proposal: Proposal = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if proposal.key != Key::ProposalV1 {
+            msg!("Proposal account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(proposal)
+    }
+
+    /// Whether the votes cast meet the governance's quorum and whether the yes votes meet its threshold
+    pub fn tally(&self, governance: &Governance) -> (bool, bool) {
+        let cast = self.yes_votes as u128 + self.no_votes as u128;
+        let quorum_met = cast * 10_000 >= governance.quorum_bps as u128 * self.quorum_base as u128;
+        let threshold_met =
+            self.yes_votes > 0 && self.yes_votes as u128 * 10_000 >= governance.threshold_bps as u128 * cast;
+
+        (quorum_met, threshold_met)
+    }
+}
+
+/// A holder's vote on a proposal. The shares voted with are locked in the proposal's vote escrow
+/// until voting ends, so the same shares can't vote twice.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct VoteRecord {
+    pub key: Key,
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub approve: bool,
+    pub amount: u64,
+}
+
+impl VoteRecord {
+    pub fn from_account_info(a: &AccountInfo) -> Result<VoteRecord, ProgramError> {
+        let vote: VoteRecord = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if vote.key != Key::VoteRecordV1 {
+            msg!("Vote record account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(vote)
+    }
+}
+
+/// The verified investors allowed to receive a vault's shares. Once a vault has an Allowlist,
+/// every instruction that moves shares out to a wallet requires a current entry for it.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Allowlist {
+    pub key: Key,
+    pub vault: Pubkey,
+    /// Who adds and removes entries, e.g. the KYC provider
+    pub compliance_authority: Pubkey,
+    /// Key whose signed KYC Attestations stand in for entries, the default pubkey for none
+    pub attester: Pubkey,
+    pub bump: u8,
+}
+
+impl Allowlist {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Allowlist, ProgramError> {
+        let allowlist: Allowlist = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if allowlist.key != Key::AllowlistV1 {
+            msg!("Allowlist account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(allowlist)
+    }
+}
+
+/// A wallet's verification, valid until `expires_ts`
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct AllowlistEntry {
+    pub key: Key,
+    pub allowlist: Pubkey,
+    pub wallet: Pubkey,
+    pub expires_ts: i64,
+    pub accredited: bool,
+    /// ISO 3166-1 alpha-2 code of the investor's country of residence
+    pub country: [u8; 2],
+}
+
+impl AllowlistEntry {
+    pub fn from_account_info(a: &AccountInfo) -> Result<AllowlistEntry, ProgramError> {
+        let entry: AllowlistEntry = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if entry.key != Key::AllowlistEntryV1 {
+            msg!("Allowlist entry account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(entry)
+    }
+}
+
+/// A wallet's verification signed off-chain by the allowlist's attester. SendShare and BuyShares accept one
+/// in place of an allowlist entry when an ed25519 program instruction earlier in the transaction verified the
+/// attester's signature of its borsh serialization.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct KycAttestation {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub expires_ts: i64,
+    pub accredited: bool,
+    pub country: [u8; 2],
+}
+
+/// When locked shares are released. Nothing vests before `cliff_ts`, after which shares vest linearly
+/// from `start_ts` until everything has vested at `end_ts`. A cliff at `end_ts` releases everything at once.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct VestingSchedule {
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+}
+
+impl VestingSchedule {
+    pub fn is_valid(&self) -> bool {
+        self.start_ts <= self.cliff_ts && self.cliff_ts <= self.end_ts
+    }
+
+    /// How many of `total` shares have vested at `now`
+    pub fn vested(&self, total: u64, now: i64) -> u64 {
+        if now < self.cliff_ts {
+            0
+        } else if now >= self.end_ts {
+            total
+        } else {
+            (total as u128 * (now - self.start_ts) as u128 / (self.end_ts - self.start_ts) as u128) as u64
+        }
+    }
+}
+
+/// Shares sent to a recipient under a vesting schedule, held in the vesting escrow until claimed
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Vesting {
+    pub key: Key,
+    pub vault: Pubkey,
+    pub recipient: Pubkey,
+    pub fraction_mint: Pubkey,
+    /// Who paid the rent for the vesting accounts and gets it back once everything is claimed
+    pub payer: Pubkey,
+    pub schedule: VestingSchedule,
+    pub total: u64,
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+impl Vesting {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Vesting, ProgramError> {
+        let vesting: Vesting = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if vesting.key != Key::VestingV1 {
+            msg!("Vesting account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(vesting)
+    }
+}
+
+/// How many wallets hold a vault's shares, counted as associated token accounts with a non-zero balance.
+/// Only share moves made through Tokrizer are counted.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct HolderRegistry {
+    pub key: Key,
+    pub vault: Pubkey,
+    pub fraction_mint: Pubkey,
+    /// 0 for no limit
+    pub max_holders: u64,
+    pub holder_count: u64,
+    pub bump: u8,
+}
+
+impl HolderRegistry {
+    pub fn from_account_info(a: &AccountInfo) -> Result<HolderRegistry, ProgramError> {
+        let registry: HolderRegistry = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if registry.key != Key::HolderRegistryV1 {
+            msg!("Holder registry account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(registry)
+    }
+
+    /// The stricter of the registry's limit and the vault's compliance profile's, ignoring either that is 0
+    pub fn holder_limit(&self, profile: Option<&ComplianceProfile>) -> u64 {
+        [self.max_holders, profile.map_or(0, |profile| profile.max_holders)]
+            .iter()
+            .copied()
+            .filter(|&max_holders| max_holders != 0)
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// Who may hold and trade a vault's shares
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum TransferRestriction {
+    /// Anyone may hold shares, unless the vault has an Allowlist
+    Open,
+    /// Shares only go to wallets on the vault's Allowlist
+    Allowlist,
+}
+
+/// The rules of a jurisdiction, e.g. Reg D 506(c), set up once and followed by any number of vaults.
+/// Share-moving instructions consult the profile of a vault instead of the vault's own settings.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct ComplianceProfile {
+    pub key: Key,
+    pub authority: Pubkey,
+    pub id: u64,
+    /// 0 for no limit, otherwise the stricter of this and the max holders of the vault's HolderRegistry applies
+    pub max_holders: u64,
+    /// Only allowlist entries marked accredited may receive shares
+    pub accredited_only: bool,
+    /// Seconds after the vault is created before holders can transfer shares to each other
+    pub lockup_duration: i64,
+    /// ISO 3166-1 alpha-2 codes investors must reside in, empty for any country
+    pub allowed_countries: Vec<[u8; 2]>,
+    pub transfer_restriction: TransferRestriction,
+    pub bump: u8,
+}
+
+impl ComplianceProfile {
+    pub fn from_account_info(a: &AccountInfo) -> Result<ComplianceProfile, ProgramError> {
+        let profile: ComplianceProfile = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if profile.key != Key::ComplianceProfileV1 {
+            msg!("Compliance profile account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(profile)
+    }
+
+    /// Whether the profile only lets shares go to wallets on the vault's Allowlist
+    pub fn requires_allowlist(&self) -> bool {
+        self.transfer_restriction != TransferRestriction::Open
+            || self.accredited_only
+            || !self.allowed_countries.is_empty()
+    }
+}
+
+/// Links a vault to the ComplianceProfile it follows, set when the vault is created
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct VaultCompliance {
+    pub key: Key,
+    pub vault: Pubkey,
+    pub profile: Pubkey,
+    /// Start of the profile's lockup
+    pub created_ts: i64,
+    pub bump: u8,
+}
+
+impl VaultCompliance {
+    pub fn from_account_info(a: &AccountInfo) -> Result<VaultCompliance, ProgramError> {
+        let compliance: VaultCompliance = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if compliance.key != Key::VaultComplianceV1 {
+            msg!("Vault compliance account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(compliance)
+    }
+}
+
+/// The program wide sanctions list. A wallet is denied while its DeniedWallet account exists.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct DenyList {
+    pub key: Key,
+    /// Who denies and clears wallets
+    pub compliance_authority: Pubkey,
+    pub bump: u8,
+}
+
+impl DenyList {
+    pub fn from_account_info(a: &AccountInfo) -> Result<DenyList, ProgramError> {
+        let deny_list: DenyList = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if deny_list.key != Key::DenyListV1 {
+            msg!("Deny list account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(deny_list)
+    }
+}
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct DeniedWallet {
+    pub key: Key,
+    pub wallet: Pubkey,
+    pub denied_ts: i64,
+}
+
+impl DeniedWallet {
+    pub fn from_account_info(a: &AccountInfo) -> Result<DeniedWallet, ProgramError> {
+        let denied: DeniedWallet = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if denied.key != Key::DeniedWalletV1 {
+            msg!("Denied wallet account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(denied)
+    }
+}
+
+/// A holder's recovery key, and the recovery it requested if any
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Recovery {
+    pub key: Key,
+    pub holder: Pubkey,
+    /// The holder's backup wallet or custodian, who requests and executes recoveries
+    pub recovery_key: Pubkey,
+    /// Seconds between a request and when it can be executed, for the holder to cancel it
+    pub delay: i64,
+    /// Where the shares go, Pubkey::default() when no recovery is requested
+    pub new_wallet: Pubkey,
+    pub requested_ts: i64,
+    pub bump: u8,
+}
+
+impl Recovery {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Recovery, ProgramError> {
+        let recovery: Recovery = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if recovery.key != Key::RecoveryV1 {
+            msg!("Recovery account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(recovery)
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.new_wallet != Pubkey::default()
+    }
+}
+
+/// Program wide settings, a single PDA created by the upgrade authority and managed by the admin
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Config {
+    pub key: Key,
+    pub admin: Pubkey,
+    /// Where protocol fees are withdrawn to
+    pub treasury: Pubkey,
+    /// Flat fee in lamports for minting an rNFT
+    pub mint_fee: u64,
+    /// Flat fee in lamports for creating a vault
+    pub vault_fee: u64,
+    /// Fee on primary share sales and buyouts, in basis points of the price
+    pub sale_fee_bps: u16,
+    /// Token metadata program MintTokrNft must be given
+    pub metadata_program: Pubkey,
+    /// Token vault program CreateVault must be given
+    pub vault_program: Pubkey,
+    /// Instructions that take the config, other than SetPaused and SetVaultPaused, fail while set
+    pub paused: bool,
+    pub bump: u8,
+}
+
+impl Config {
+    pub fn from_account_info(a: &AccountInfo) -> Result<Config, ProgramError> {
+        let config: Config = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if config.key != Key::ConfigV1 {
+            msg!("Config account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(config)
+    }
+}
+
+/// What a wallet is allowed to do, granted by the config admin or another Admin
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum Role {
+    /// Grants and revokes roles
+    Admin,
+    /// Mints rNFTs and creates vaults, e.g. a title company
+    Tokenizer,
+    /// Sets up and funds income distributions
+    PropertyManager,
+    /// Manages compliance profiles, allowlists and the deny list, and forces transfers
+    Compliance,
+    /// Pauses and unpauses the program and single vaults
+    Emergency,
+}
+
+/// A role held by a wallet, one PDA per wallet and role. The role is revoked by closing it.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct RoleAssignment {
+    pub key: Key,
+    pub wallet: Pubkey,
+    pub role: Role,
+    pub granted_by: Pubkey,
+    pub granted_ts: i64,
+}
+
+impl RoleAssignment {
+    pub fn from_account_info(a: &AccountInfo) -> Result<RoleAssignment, ProgramError> {
+        let assignment: RoleAssignment = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if assignment.key != Key::RoleV1 {
+            msg!("Role account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(assignment)
+    }
+}
+
+/// A vault paused on its own, created the first time it is paused. Every instruction on the vault
+/// takes this account and fails while `paused` is set.
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct VaultPause {
+    pub key: Key,
+    pub vault: Pubkey,
+    pub paused: bool,
+    pub bump: u8,
+}
+
+impl VaultPause {
+    pub fn from_account_info(a: &AccountInfo) -> Result<VaultPause, ProgramError> {
+        let vault_pause: VaultPause = try_from_slice_unchecked(&a.data.borrow_mut())?;
+        if vault_pause.key != Key::VaultPauseV1 {
+            msg!("Vault pause account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(vault_pause)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distribution(total_staked: u64) -> Distribution {
+        Distribution {
+            key: Key::DistributionV1,
+            vault: Pubkey::default(),
+            fraction_mint: Pubkey::default(),
+            payment_mint: Pubkey::default(),
+            manager: Pubkey::default(),
+            total_staked,
+            income_per_share: 0,
+            bump: 0,
+        }
+    }
+
+    fn stake(amount: u64, income_per_share: u128) -> HolderStake {
+        let mut stake = HolderStake {
+            key: Key::HolderStakeV1,
+            distribution: Pubkey::default(),
+            holder: Pubkey::default(),
+            amount: 0,
+            income_debt: 0,
+        };
+        stake.restake(amount, income_per_share, 0).unwrap();
+        stake
+    }
+
+    fn owed(stake: &HolderStake, distribution: &Distribution) -> u128 {
+        stake.pending_income(distribution.income_per_share).unwrap() / INCOME_PER_SHARE_PRECISION
+    }
+
+    #[test]
+    fn income_is_split_pro_rata_across_staked_shares() {
+        let mut distribution = distribution(4);
+        let small = stake(1, 0);
+        let large = stake(3, 0);
+
+        distribution.credit_income(100).unwrap();
+
+        assert_eq!(owed(&small, &distribution), 25);
+        assert_eq!(owed(&large, &distribution), 75);
+    }
+
+    #[test]
+    fn late_stakers_do_not_earn_earlier_income() {
+        let mut distribution = distribution(1);
+        let early = stake(1, 0);
+        distribution.credit_income(100).unwrap();
+
+        let late = stake(1, distribution.income_per_share);
+        distribution.total_staked = 2;
+        distribution.credit_income(100).unwrap();
+
+        assert_eq!(owed(&early, &distribution), 150);
+        assert_eq!(owed(&late, &distribution), 50);
+    }
+
+    #[test]
+    fn unpaid_fractions_carry_over() {
+        let mut distribution = distribution(2);
+        let mut holder = stake(1, 0);
+
+        // Half a token is owed but can't be paid yet
+        distribution.credit_income(1).unwrap();
+        let pending = holder.pending_income(distribution.income_per_share).unwrap();
+        assert_eq!(pending / INCOME_PER_SHARE_PRECISION, 0);
+        holder.restake(1, distribution.income_per_share, pending).unwrap();
+
+        distribution.credit_income(1).unwrap();
+        assert_eq!(owed(&holder, &distribution), 1);
+    }
+
+    #[test]
+    fn restaking_after_payment_leaves_nothing_owed() {
+        let mut distribution = distribution(2);
+        let mut holder = stake(2, 0);
+        distribution.credit_income(10).unwrap();
+
+        let pending = holder.pending_income(distribution.income_per_share).unwrap();
+        holder.restake(5, distribution.income_per_share, pending).unwrap();
+
+        assert_eq!(owed(&holder, &distribution), 0);
+    }
+
+    #[test]
+    fn income_needs_staked_shares() {
+        assert_eq!(distribution(0).credit_income(1), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn income_overflow_is_an_error() {
+        let mut distribution = distribution(1);
+        distribution.income_per_share = u128::MAX;
+        assert_eq!(distribution.credit_income(1), Err(ProgramError::InvalidArgument));
+
+        let holder = stake(2, 0);
+        assert_eq!(holder.pending_income(u128::MAX), Err(ProgramError::InvalidArgument));
+    }
+
+    fn registry(max_holders: u64) -> HolderRegistry {
+        HolderRegistry {
+            key: Key::HolderRegistryV1,
+            vault: Pubkey::default(),
+            fraction_mint: Pubkey::default(),
+            max_holders,
+            holder_count: 0,
+            bump: 0,
+        }
+    }
+
+    fn profile(max_holders: u64) -> ComplianceProfile {
+        ComplianceProfile {
+            key: Key::ComplianceProfileV1,
+            authority: Pubkey::default(),
+            id: 0,
+            max_holders,
+            accredited_only: false,
+            lockup_duration: 0,
+            allowed_countries: vec![],
+            transfer_restriction: TransferRestriction::Open,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn stricter_holder_limit_applies() {
+        assert_eq!(registry(100).holder_limit(None), 100);
+        assert_eq!(registry(100).holder_limit(Some(&profile(35))), 35);
+        assert_eq!(registry(35).holder_limit(Some(&profile(100))), 35);
+    }
+
+    #[test]
+    fn unlimited_profile_keeps_the_registry_limit() {
+        assert_eq!(registry(100).holder_limit(Some(&profile(0))), 100);
+        assert_eq!(registry(0).holder_limit(Some(&profile(35))), 35);
+        assert_eq!(registry(0).holder_limit(Some(&profile(0))), 0);
+    }
+
+    fn governance(quorum_bps: u16, threshold_bps: u16) -> Governance {
+        Governance {
+            key: Key::GovernanceV1,
+            vault: Pubkey::default(),
+            authority: Pubkey::default(),
+            fraction_mint: Pubkey::default(),
+            quorum_bps,
+            threshold_bps,
+            voting_period: 0,
+            proposal_count: 0,
+            bump: 0,
+        }
+    }
+
+    fn proposal(quorum_base: u64, yes_votes: u64, no_votes: u64) -> Proposal {
+        Proposal {
+            key: Key::ProposalV1,
+            governance: Pubkey::default(),
+            proposer: Pubkey::default(),
+            index: 0,
+            action: ProposalAction::CombineVault,
+            start_ts: 0,
+            end_ts: 0,
+            yes_votes,
+            no_votes,
+            state: ProposalState::Voting,
+            bump: 0,
+            quorum_base,
+        }
+    }
+
+    #[test]
+    fn quorum_is_measured_against_the_base_at_creation() {
+        let governance = governance(2_000, 5_000);
+
+        assert_eq!(proposal(1_000, 150, 49).tally(&governance), (false, true));
+        assert_eq!(proposal(1_000, 150, 50).tally(&governance), (true, true));
+    }
+
+    #[test]
+    fn threshold_is_measured_against_the_votes_cast() {
+        let governance = governance(0, 6_000);
+
+        assert_eq!(proposal(1_000, 60, 40).tally(&governance), (true, true));
+        assert_eq!(proposal(1_000, 59, 41).tally(&governance), (true, false));
+    }
+
+    #[test]
+    fn no_votes_never_pass() {
+        let governance = governance(0, 0);
+
+        assert_eq!(proposal(1_000, 0, 0).tally(&governance), (true, false));
+    }
+
+    #[test]
+    fn tally_does_not_overflow() {
+        let governance = governance(10_000, 10_000);
+
+        assert_eq!(proposal(u64::MAX, u64::MAX, 0).tally(&governance), (true, true));
+    }
+
+    fn schedule(start_ts: i64, cliff_ts: i64, end_ts: i64) -> VestingSchedule {
+        VestingSchedule { start_ts, cliff_ts, end_ts }
+    }
+
+    #[test]
+    fn nothing_vests_before_the_cliff() {
+        let schedule = schedule(100, 150, 200);
+
+        assert_eq!(schedule.vested(1_000, 0), 0);
+        assert_eq!(schedule.vested(1_000, 149), 0);
+    }
+
+    #[test]
+    fn vesting_is_linear_from_the_start_once_past_the_cliff() {
+        let schedule = schedule(100, 150, 200);
+
+        assert_eq!(schedule.vested(1_000, 150), 500);
+        assert_eq!(schedule.vested(1_000, 175), 750);
+        assert_eq!(schedule.vested(1_000, 199), 990);
+    }
+
+    #[test]
+    fn everything_vests_at_the_end() {
+        let schedule = schedule(100, 150, 200);
+
+        assert_eq!(schedule.vested(1_000, 200), 1_000);
+        assert_eq!(schedule.vested(1_000, i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn cliff_at_the_end_releases_everything_at_once() {
+        let schedule = schedule(100, 200, 200);
+
+        assert_eq!(schedule.vested(1_000, 199), 0);
+        assert_eq!(schedule.vested(1_000, 200), 1_000);
+    }
+
+    #[test]
+    fn vesting_a_large_grant_does_not_overflow() {
+        let schedule = schedule(0, 0, 4);
+
+        assert_eq!(schedule.vested(u64::MAX, 2), u64::MAX / 2);
+    }
+
+    #[test]
+    fn cliff_must_fall_within_the_schedule() {
+        assert!(schedule(100, 100, 100).is_valid());
+        assert!(!schedule(100, 99, 200).is_valid());
+        assert!(!schedule(100, 201, 200).is_valid());
+    }
+}
@@ -0,0 +1,341 @@
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable, ed25519_program,
+    entrypoint::ProgramResult,
+    hash::hashv,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use spl_token::{instruction::initialize_account, state::Account};
+
+/// Create a rent exempt account at a PDA of this program
+pub fn create_program_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    rent: &Rent,
+    size: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            new_account.key,
+            rent.minimum_balance(size),
+            size as u64,
+            program_id,
+        ),
+        &[payer.clone(), new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+/// Create a token account at a PDA of this program and initialize it for the mint and owner
+#[allow(clippy::too_many_arguments)]
+pub fn create_token_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    rent_program: &AccountInfo<'a>,
+    rent: &Rent,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            new_account.key,
+            rent.minimum_balance(Account::LEN),
+            Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        &[payer.clone(), new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+
+    invoke(
+        &initialize_account(&spl_token::id(), new_account.key, mint.key, owner.key)?,
+        &[
+            new_account.clone(),
+            mint.clone(),
+            owner.clone(),
+            rent_program.clone(),
+        ],
+    )
+}
+
+pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner != owner {
+        msg!("Account {} is not owned by {}", account.key, owner);
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(())
+}
+
+/// Check the account is the PDA for the given seeds and return its bump
+pub fn assert_derivation(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    seeds: &[&[u8]],
+) -> Result<u8, ProgramError> {
+    let (key, bump) = Pubkey::find_program_address(seeds, program_id);
+    if key != *account.key {
+        msg!("Derived PDA key mismatch for {}", account.key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(bump)
+}
+
+/// Close an account owned by this program, sending its lamports to the receiver
+pub fn close_program_account(account: &AccountInfo, receiver: &AccountInfo) -> ProgramResult {
+    let lamports = account.lamports();
+    **receiver.lamports.borrow_mut() = receiver
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
+    **account.lamports.borrow_mut() = 0;
+
+    let mut data = account.data.borrow_mut();
+    for byte in data.iter_mut() {
+        *byte = 0;
+    }
+
+    Ok(())
+}
+
+/// Leaf of a snapshot Merkle tree. Leaves and nodes are prefixed differently so a node can't pass as a leaf.
+pub fn snapshot_leaf(holder: &Pubkey, balance: u64) -> [u8; 32] {
+    hashv(&[&[0], holder.as_ref(), &balance.to_le_bytes()]).to_bytes()
+}
+
+/// Check a leaf is in the tree. Each pair of nodes is hashed in sorted order so the proof needs no path bits.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&[1], &node, sibling]).to_bytes()
+        } else {
+            hashv(&[&[1], sibling, &node]).to_bytes()
+        }
+    });
+
+    computed == *root
+}
+
+/// Messages whose signature by `signer` was verified by ed25519 program instructions earlier in the transaction.
+/// Only signatures whose key and message are in the ed25519 instruction's own data are counted.
+pub fn ed25519_signed_messages(instructions_sysvar: &AccountInfo, signer: &Pubkey) -> Result<Vec<Vec<u8>>, ProgramError> {
+    let current = load_current_index_checked(instructions_sysvar)? as usize;
+
+    let mut messages = Vec::new();
+    for index in 0..current {
+        let instruction = load_instruction_at_checked(index, instructions_sysvar)?;
+        if instruction.program_id != ed25519_program::id() {
+            continue;
+        }
+
+        // A signature count and a padding byte, then seven u16 offsets per signature
+        let data = &instruction.data;
+        let count = data.first().copied().unwrap_or(0) as usize;
+        for offsets in data.get(2..2 + count * 14).unwrap_or(&[]).chunks(14) {
+            let read = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+            // The instruction indexes of the signature, key and message, u16::MAX for this instruction
+            if read(2) != u16::MAX || read(6) != u16::MAX || read(12) != u16::MAX {
+                continue;
+            }
+
+            let key = read(4) as usize;
+            let (message, size) = (read(8) as usize, read(10) as usize);
+            if data.get(key..key + 32) == Some(signer.as_ref()) {
+                if let Some(message) = data.get(message..message + size) {
+                    messages.push(message.to_vec());
+                }
+            }
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Check `authority` signed and is the upgrade authority of this program. `program_data` is the program's
+/// ProgramData account, which starts with a u32 tag of 3, the deployment slot and the optional upgrade authority.
+pub fn assert_upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    let (program_data_key, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if *program_data.key != program_data_key {
+        msg!("Not this program's ProgramData account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let data = program_data.data.borrow();
+    let upgrade_authority = match data.get(0..45) {
+        Some(header) if header[0..4] == 3u32.to_le_bytes() && header[12] == 1 => Pubkey::new(&header[13..45]),
+        _ => {
+            msg!("Program is not upgradeable");
+            return Err(ProgramError::InvalidAccountData);
+        }
+    };
+
+    if !authority.is_signer || upgrade_authority != *authority.key {
+        msg!("Program upgrade authority must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::sysvar::{
+        self,
+        instructions::{construct_instructions_data, store_current_index, BorrowedInstruction},
+    };
+
+    fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        if left <= right {
+            hashv(&[&[1], left, right]).to_bytes()
+        } else {
+            hashv(&[&[1], right, left]).to_bytes()
+        }
+    }
+
+    #[test]
+    fn merkle_proof_of_every_leaf_verifies() {
+        let holders: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> =
+            holders.iter().enumerate().map(|(i, holder)| snapshot_leaf(holder, i as u64 + 1)).collect();
+        let (left, right) = (node(&leaves[0], &leaves[1]), node(&leaves[2], &leaves[3]));
+        let root = node(&left, &right);
+
+        assert!(verify_merkle_proof(&[leaves[1], right], &root, leaves[0]));
+        assert!(verify_merkle_proof(&[leaves[0], right], &root, leaves[1]));
+        assert!(verify_merkle_proof(&[leaves[3], left], &root, leaves[2]));
+        assert!(verify_merkle_proof(&[leaves[2], left], &root, leaves[3]));
+    }
+
+    #[test]
+    fn merkle_proof_rejects_a_wrong_balance() {
+        let holders = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves = [snapshot_leaf(&holders[0], 10), snapshot_leaf(&holders[1], 20)];
+        let root = node(&leaves[0], &leaves[1]);
+
+        assert!(!verify_merkle_proof(&[leaves[1]], &root, snapshot_leaf(&holders[0], 11)));
+        assert!(!verify_merkle_proof(&[leaves[1]], &root, snapshot_leaf(&holders[1], 10)));
+    }
+
+    #[test]
+    fn single_holder_tree_is_its_own_root() {
+        let leaf = snapshot_leaf(&Pubkey::new_unique(), 100);
+
+        assert!(verify_merkle_proof(&[], &leaf, leaf));
+        assert!(!verify_merkle_proof(&[], &leaf, snapshot_leaf(&Pubkey::new_unique(), 100)));
+    }
+
+    /// Data of an ed25519 program instruction checking one signature of `message` by `key`, with the
+    /// signature, key and message in the instruction at `instruction_index`
+    fn ed25519_data(key: &Pubkey, message: &[u8], instruction_index: u16) -> Vec<u8> {
+        let (key_offset, signature_offset, message_offset) = (16u16, 48u16, 112u16);
+        let mut data = vec![1, 0];
+        for field in [
+            signature_offset,
+            instruction_index,
+            key_offset,
+            instruction_index,
+            message_offset,
+            message.len() as u16,
+            instruction_index,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(key.as_ref());
+        data.extend_from_slice(&[0; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    /// What `ed25519_signed_messages` finds for `signer` while running the instruction at `current`
+    fn signed_messages(instructions: &[(Pubkey, Vec<u8>)], current: u16, signer: &Pubkey) -> Vec<Vec<u8>> {
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|(program_id, data)| BorrowedInstruction {
+                program_id,
+                accounts: vec![],
+                data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, current);
+
+        let (key, owner, mut lamports) = (sysvar::instructions::id(), sysvar::id(), 0);
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        ed25519_signed_messages(&account, signer).unwrap()
+    }
+
+    #[test]
+    fn message_signed_earlier_in_the_transaction_is_found() {
+        let attester = Pubkey::new_unique();
+        let instructions = [
+            (ed25519_program::id(), ed25519_data(&attester, b"attestation", u16::MAX)),
+            (crate::id(), vec![]),
+        ];
+
+        assert_eq!(signed_messages(&instructions, 1, &attester), vec![b"attestation".to_vec()]);
+    }
+
+    #[test]
+    fn messages_of_other_signers_are_ignored() {
+        let instructions = [
+            (ed25519_program::id(), ed25519_data(&Pubkey::new_unique(), b"attestation", u16::MAX)),
+            (crate::id(), vec![]),
+        ];
+
+        assert!(signed_messages(&instructions, 1, &Pubkey::new_unique()).is_empty());
+    }
+
+    #[test]
+    fn only_instructions_before_the_current_one_count() {
+        let attester = Pubkey::new_unique();
+        let instructions = [
+            (crate::id(), vec![]),
+            (ed25519_program::id(), ed25519_data(&attester, b"attestation", u16::MAX)),
+        ];
+
+        assert!(signed_messages(&instructions, 0, &attester).is_empty());
+    }
+
+    #[test]
+    fn signatures_over_data_of_other_instructions_are_ignored() {
+        let attester = Pubkey::new_unique();
+        let instructions = [
+            (ed25519_program::id(), ed25519_data(&attester, b"attestation", 1)),
+            (crate::id(), vec![]),
+        ];
+
+        assert!(signed_messages(&instructions, 1, &attester).is_empty());
+    }
+
+    #[test]
+    fn other_programs_are_not_taken_for_the_ed25519_program() {
+        let attester = Pubkey::new_unique();
+        let instructions = [
+            (Pubkey::new_unique(), ed25519_data(&attester, b"attestation", u16::MAX)),
+            (crate::id(), vec![]),
+        ];
+
+        assert!(signed_messages(&instructions, 1, &attester).is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+#![allow(dead_code)]
+
+use borsh::BorshSerialize;
+use mpl_token_vault::state::{Key as VaultKey, Vault, VaultState, MAX_VAULT_SIZE};
+use solana_program::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+    transport::TransportError,
+};
+use tokrizer::{
+    instruction::TokrizerInstruction,
+    state::{
+        Config, DeniedWallet, Key, Role, RoleAssignment, VaultPause, CONFIG_PREFIX, CONFIG_SIZE,
+        DENIED_WALLET_PREFIX, DENIED_WALLET_SIZE, ROLE_PREFIX, ROLE_SIZE, VAULT_PAUSE_PREFIX, VAULT_PAUSE_SIZE,
+    },
+};
+
+// The native processor of solana-program-test 1.9 can't grow account data in a cross-program
+// invocation, so tests set up Tokrizer's accounts directly instead of creating them through it.
+
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new("tokrizer", tokrizer::id(), processor!(tokrizer::processor::process))
+}
+
+/// Add an account owned by Tokrizer holding `state`, padded to `size`
+pub fn add_state<T: BorshSerialize>(program_test: &mut ProgramTest, address: Pubkey, state: &T, size: usize) {
+    let mut data = state.try_to_vec().unwrap();
+    data.resize(size, 0);
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: tokrizer::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_PREFIX.as_bytes()], &tokrizer::id()).0
+}
+
+pub fn role_address(wallet: &Pubkey, role: Role) -> Pubkey {
+    Pubkey::find_program_address(&[ROLE_PREFIX.as_bytes(), wallet.as_ref(), &[role as u8]], &tokrizer::id()).0
+}
+
+pub fn vault_pause_address(vault: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[VAULT_PAUSE_PREFIX.as_bytes(), vault.as_ref()], &tokrizer::id()).0
+}
+
+pub fn denied_address(wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[DENIED_WALLET_PREFIX.as_bytes(), wallet.as_ref()], &tokrizer::id()).0
+}
+
+/// Put the wallet on the deny list, as Deny Wallet would
+pub fn add_denied(program_test: &mut ProgramTest, wallet: &Pubkey) {
+    let denied = DeniedWallet {
+        key: Key::DeniedWalletV1,
+        wallet: *wallet,
+        denied_ts: 0,
+    };
+    add_state(program_test, denied_address(wallet), &denied, DENIED_WALLET_SIZE);
+}
+
+/// Add an SPL token account of `mint` owned by `owner`
+pub fn add_token_account(program_test: &mut ProgramTest, address: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+pub fn config(admin: &Pubkey) -> Config {
+    let (_, bump) = Pubkey::find_program_address(&[CONFIG_PREFIX.as_bytes()], &tokrizer::id());
+    Config {
+        key: Key::ConfigV1,
+        admin: *admin,
+        treasury: *admin,
+        mint_fee: 0,
+        vault_fee: 0,
+        sale_fee_bps: 0,
+        metadata_program: mpl_token_metadata::id(),
+        vault_program: mpl_token_vault::id(),
+        paused: false,
+        bump,
+    }
+}
+
+pub fn add_config(program_test: &mut ProgramTest, config: &Config) {
+    add_state(program_test, config_address(), config, CONFIG_SIZE);
+}
+
+/// Give the wallet the role, as Grant Role would
+pub fn add_role(program_test: &mut ProgramTest, wallet: &Pubkey, role: Role) {
+    add_role_account(program_test, role_address(wallet, role), wallet, role);
+}
+
+/// Store a role assignment at any address, e.g. to check a mismatched one is rejected
+pub fn add_role_account(program_test: &mut ProgramTest, address: Pubkey, wallet: &Pubkey, role: Role) {
+    let assignment = RoleAssignment {
+        key: Key::RoleV1,
+        wallet: *wallet,
+        role,
+        granted_by: *wallet,
+        granted_ts: 0,
+    };
+    add_state(program_test, address, &assignment, ROLE_SIZE);
+}
+
+/// Pause or unpause the vault, as Set Vault Paused would
+pub fn add_vault_pause(program_test: &mut ProgramTest, vault: &Pubkey, paused: bool) {
+    let (address, bump) =
+        Pubkey::find_program_address(&[VAULT_PAUSE_PREFIX.as_bytes(), vault.as_ref()], &tokrizer::id());
+    let vault_pause = VaultPause {
+        key: Key::VaultPauseV1,
+        vault: *vault,
+        paused,
+        bump,
+    };
+    add_state(program_test, address, &vault_pause, VAULT_PAUSE_SIZE);
+}
+
+/// Add a token vault account holding `vault`, owned by `owner` so tests can also forge one
+pub fn add_vault_account(program_test: &mut ProgramTest, address: Pubkey, vault: &Vault, owner: &Pubkey) {
+    let mut data = vault.try_to_vec().unwrap();
+    data.resize(MAX_VAULT_SIZE, 0);
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: *owner,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+/// An active vault over the fraction mint
+pub fn active_vault(fraction_mint: &Pubkey, authority: &Pubkey) -> Vault {
+    Vault {
+        key: VaultKey::VaultV1,
+        token_program: spl_token::id(),
+        fraction_mint: *fraction_mint,
+        authority: *authority,
+        fraction_treasury: Pubkey::new_unique(),
+        redeem_treasury: Pubkey::new_unique(),
+        allow_further_share_creation: false,
+        pricing_lookup_address: Pubkey::new_unique(),
+        token_type_count: 1,
+        state: VaultState::Active,
+        locked_price_per_share: 0,
+    }
+}
+
+pub fn tokrizer_instruction(instruction: TokrizerInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+    Instruction::new_with_bytes(tokrizer::id(), &instruction.try_to_vec().unwrap(), accounts)
+}
+
+/// Send the instructions signed by the payer and the given signers
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransportError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction =
+        Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// The program error a transaction failed with
+pub fn program_error(result: Result<(), TransportError>) -> ProgramError {
+    match result.unwrap_err() {
+        TransportError::TransactionError(TransactionError::InstructionError(_, error)) => match error {
+            InstructionError::Custom(code) => ProgramError::Custom(code),
+            InstructionError::IllegalOwner => ProgramError::IllegalOwner,
+            InstructionError::InvalidArgument => ProgramError::InvalidArgument,
+            InstructionError::InvalidAccountData => ProgramError::InvalidAccountData,
+            InstructionError::InvalidSeeds => ProgramError::InvalidSeeds,
+            InstructionError::MissingRequiredSignature => ProgramError::MissingRequiredSignature,
+            InstructionError::NotEnoughAccountKeys => ProgramError::NotEnoughAccountKeys,
+            InstructionError::UninitializedAccount => ProgramError::UninitializedAccount,
+            error => panic!("unexpected instruction error {:?}", error),
+        },
+        error => panic!("expected an instruction error, got {:?}", error),
+    }
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use solana_program::{
+    instruction::AccountMeta, instruction::Instruction, program_error::ProgramError, program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_program_test::{tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token::state::Account as TokenAccount;
+use tokrizer::{
+    instruction::TokrizerInstruction,
+    state::{
+        Key, Offering, OfferingState, Purchase, OFFERING_PREFIX, OFFERING_SHARES_PREFIX, OFFERING_SIZE,
+        PROCEEDS_PREFIX, PURCHASE_PREFIX, PURCHASE_SIZE,
+    },
+};
+
+const SHARES: u64 = 10;
+
+const PAID: u64 = 500;
+
+fn tokrizer_address(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &tokrizer::id()).0
+}
+
+/// The accounts of an escrowed offering in which the buyer bought SHARES for PAID
+struct Sale {
+    vault: Pubkey,
+    authority: Keypair,
+    buyer: Pubkey,
+    payment_mint: Pubkey,
+    offering: Pubkey,
+    buyer_payment_account: Pubkey,
+    authority_payment_account: Pubkey,
+    fraction_treasury: Pubkey,
+}
+
+impl Sale {
+    fn new() -> Sale {
+        Sale {
+            vault: Pubkey::new_unique(),
+            authority: Keypair::new(),
+            buyer: Pubkey::new_unique(),
+            payment_mint: Pubkey::new_unique(),
+            offering: Pubkey::default(),
+            buyer_payment_account: Pubkey::new_unique(),
+            authority_payment_account: Pubkey::new_unique(),
+            fraction_treasury: Pubkey::new_unique(),
+        }
+    }
+
+    fn proceeds(&self) -> Pubkey {
+        tokrizer_address(&[PROCEEDS_PREFIX.as_bytes(), self.offering.as_ref()])
+    }
+
+    fn offering_shares(&self) -> Pubkey {
+        tokrizer_address(&[OFFERING_SHARES_PREFIX.as_bytes(), self.offering.as_ref()])
+    }
+
+    /// A program with the offering finalized to `state`, its proceeds paid out if it succeeded
+    fn program_test(&mut self, state: OfferingState) -> ProgramTest {
+        let mut program_test = program_test();
+        add_config(&mut program_test, &config(&Pubkey::new_unique()));
+
+        let fraction_mint = Pubkey::new_unique();
+        let mut vault = active_vault(&fraction_mint, &self.authority.pubkey());
+        vault.fraction_treasury = self.fraction_treasury;
+        add_vault_account(&mut program_test, self.vault, &vault, &mpl_token_vault::id());
+
+        let (offering, bump) =
+            Pubkey::find_program_address(&[OFFERING_PREFIX.as_bytes(), self.vault.as_ref()], &tokrizer::id());
+        self.offering = offering;
+        let offering_state = Offering {
+            key: Key::OfferingV1,
+            vault: self.vault,
+            authority: self.authority.pubkey(),
+            payer: self.authority.pubkey(),
+            fraction_mint,
+            payment_mint: self.payment_mint,
+            price_per_share: PAID / SHARES,
+            start_ts: 0,
+            end_ts: 0,
+            max_shares_per_wallet: 0,
+            shares_offered: SHARES,
+            shares_sold: SHARES,
+            bump,
+            min_shares: SHARES,
+            state,
+            open_purchases: 1,
+            nonce: 0,
+        };
+        add_state(&mut program_test, offering, &offering_state, OFFERING_SIZE);
+
+        let purchase = Purchase {
+            key: Key::PurchaseV1,
+            offering,
+            buyer: self.buyer,
+            offering_nonce: 0,
+            shares_purchased: SHARES,
+            amount_paid: PAID,
+        };
+        add_state(
+            &mut program_test,
+            tokrizer_address(&[PURCHASE_PREFIX.as_bytes(), offering.as_ref(), self.buyer.as_ref()]),
+            &purchase,
+            PURCHASE_SIZE,
+        );
+
+        let escrowed = if state == OfferingState::Succeeded { 0 } else { PAID };
+        add_token_account(&mut program_test, self.proceeds(), &self.payment_mint, &offering, escrowed);
+        add_token_account(&mut program_test, self.offering_shares(), &fraction_mint, &offering, SHARES);
+        add_token_account(&mut program_test, self.fraction_treasury, &fraction_mint, &Pubkey::new_unique(), 0);
+        add_token_account(&mut program_test, self.buyer_payment_account, &self.payment_mint, &self.buyer, 0);
+        add_token_account(
+            &mut program_test,
+            self.authority_payment_account,
+            &self.payment_mint,
+            &self.authority.pubkey(),
+            PAID - escrowed,
+        );
+        program_test
+    }
+
+    /// Refund the buyer from `refund_source`, with the vault authority signing when given
+    fn refund(&self, refund_source: Pubkey, vault_authority: Option<&Pubkey>) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.buyer, false),
+            AccountMeta::new(self.buyer_payment_account, false),
+            AccountMeta::new(self.offering, false),
+            AccountMeta::new_readonly(self.vault, false),
+            AccountMeta::new(
+                tokrizer_address(&[PURCHASE_PREFIX.as_bytes(), self.offering.as_ref(), self.buyer.as_ref()]),
+                false,
+            ),
+            AccountMeta::new(self.offering_shares(), false),
+            AccountMeta::new(refund_source, false),
+            AccountMeta::new(self.fraction_treasury, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(denied_address(&self.buyer), false),
+            AccountMeta::new_readonly(vault_pause_address(&self.vault), false),
+            AccountMeta::new_readonly(config_address(), false),
+        ];
+        if let Some(vault_authority) = vault_authority {
+            accounts.push(AccountMeta::new_readonly(*vault_authority, true));
+        }
+        tokrizer_instruction(TokrizerInstruction::RefundPurchase, accounts)
+    }
+}
+
+async fn balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    TokenAccount::unpack(&account.data).unwrap().amount
+}
+
+#[tokio::test]
+async fn denied_buyer_is_refunded_from_a_failed_offering() {
+    let mut sale = Sale::new();
+    let mut program_test = sale.program_test(OfferingState::Failed);
+    add_denied(&mut program_test, &sale.buyer);
+    let mut context = program_test.start_with_context().await;
+
+    send(&mut context, &[sale.refund(sale.proceeds(), None)], &[]).await.unwrap();
+
+    assert_eq!(balance(&mut context, sale.buyer_payment_account).await, PAID);
+    assert_eq!(balance(&mut context, sale.fraction_treasury).await, SHARES);
+}
+
+#[tokio::test]
+async fn vault_authority_refunds_a_buyer_denied_after_a_successful_offering() {
+    let mut sale = Sale::new();
+    let mut program_test = sale.program_test(OfferingState::Succeeded);
+    add_denied(&mut program_test, &sale.buyer);
+    let mut context = program_test.start_with_context().await;
+
+    let authority = sale.authority.pubkey();
+    let instruction = sale.refund(sale.authority_payment_account, Some(&authority));
+    send(&mut context, &[instruction], &[&sale.authority]).await.unwrap();
+
+    assert_eq!(balance(&mut context, sale.buyer_payment_account).await, PAID);
+    assert_eq!(balance(&mut context, sale.authority_payment_account).await, 0);
+    assert_eq!(balance(&mut context, sale.fraction_treasury).await, SHARES);
+
+    let account = context.banks_client.get_account(sale.offering).await.unwrap().unwrap();
+    let offering = Offering::deserialize(&mut &account.data[..]).unwrap();
+    assert_eq!(offering.open_purchases, 0);
+}
+
+#[tokio::test]
+async fn successful_offering_only_refunds_denied_buyers() {
+    let mut sale = Sale::new();
+    let program_test = sale.program_test(OfferingState::Succeeded);
+    let mut context = program_test.start_with_context().await;
+
+    let authority = sale.authority.pubkey();
+    let instruction = sale.refund(sale.authority_payment_account, Some(&authority));
+    let result = send(&mut context, &[instruction], &[&sale.authority]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidAccountData);
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use common::*;
+use mpl_token_vault::state::{Key as VaultKey, Vault, VaultState, MAX_VAULT_SIZE};
+use solana_program::{instruction::AccountMeta, instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    system_program, sysvar,
+};
+use tokrizer::{
+    error::TokrizerError,
+    instruction::{PauseArgs, TokrizerInstruction},
+    state::{Allowlist, Key, Role, VaultPause, ALLOWLIST_PREFIX, ALLOWLIST_SIZE},
+};
+
+const VAULT: Pubkey = Pubkey::new_from_array([7; 32]);
+
+fn allowlist_address() -> Pubkey {
+    Pubkey::find_program_address(&[ALLOWLIST_PREFIX.as_bytes(), VAULT.as_ref()], &tokrizer::id()).0
+}
+
+/// A program with an allowlist of VAULT managed by a Compliance role holder, so Set Attester is an
+/// instruction on the vault that also takes the config
+fn program_with_allowlist(compliance_authority: &Pubkey) -> ProgramTest {
+    let mut program_test = program_test();
+    let allowlist = Allowlist {
+        key: Key::AllowlistV1,
+        vault: VAULT,
+        compliance_authority: *compliance_authority,
+        attester: Pubkey::default(),
+        bump: 0,
+    };
+    add_state(&mut program_test, allowlist_address(), &allowlist, ALLOWLIST_SIZE);
+    add_role(&mut program_test, compliance_authority, Role::Compliance);
+    program_test
+}
+
+/// Add VAULT as a token vault account
+fn add_vault(program_test: &mut ProgramTest) {
+    let vault = Vault {
+        key: VaultKey::VaultV1,
+        token_program: spl_token::id(),
+        fraction_mint: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        fraction_treasury: Pubkey::new_unique(),
+        redeem_treasury: Pubkey::new_unique(),
+        allow_further_share_creation: false,
+        pricing_lookup_address: Pubkey::new_unique(),
+        token_type_count: 1,
+        state: VaultState::Active,
+        locked_price_per_share: 0,
+    };
+    let mut data = vault.try_to_vec().unwrap();
+    data.resize(MAX_VAULT_SIZE, 0);
+    program_test.add_account(
+        VAULT,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: mpl_token_vault::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+fn set_attester(compliance_authority: &Pubkey, vault_pause: Pubkey) -> Instruction {
+    tokrizer_instruction(
+        TokrizerInstruction::SetAttester,
+        vec![
+            AccountMeta::new_readonly(*compliance_authority, true),
+            AccountMeta::new(allowlist_address(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(role_address(compliance_authority, Role::Compliance), false),
+            AccountMeta::new_readonly(vault_pause, false),
+        ],
+    )
+}
+
+fn set_vault_paused(emergency_authority: &Pubkey, paused: bool) -> Instruction {
+    tokrizer_instruction(
+        TokrizerInstruction::SetVaultPaused(PauseArgs { paused }),
+        vec![
+            AccountMeta::new(*emergency_authority, true),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(role_address(emergency_authority, Role::Emergency), false),
+            AccountMeta::new_readonly(VAULT, false),
+            AccountMeta::new(vault_pause_address(&VAULT), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn paused_vault_rejects_instructions_on_it() {
+    let compliance = Keypair::new();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_vault_pause(&mut program_test, &VAULT, true);
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = set_attester(&compliance.pubkey(), vault_pause_address(&VAULT));
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), TokrizerError::VaultPaused.into());
+}
+
+#[tokio::test]
+async fn unpaused_vault_accepts_instructions_on_it() {
+    let compliance = Keypair::new();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_vault_pause(&mut program_test, &VAULT, false);
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = set_attester(&compliance.pubkey(), vault_pause_address(&VAULT));
+    send(&mut context, &[instruction], &[&compliance]).await.unwrap();
+}
+
+#[tokio::test]
+async fn vault_pause_account_must_be_the_vaults() {
+    let compliance = Keypair::new();
+    let other_vault = Pubkey::new_unique();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_vault_pause(&mut program_test, &VAULT, true);
+    add_vault_pause(&mut program_test, &other_vault, false);
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = set_attester(&compliance.pubkey(), vault_pause_address(&other_vault));
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), ProgramError::InvalidSeeds);
+}
+
+#[tokio::test]
+async fn paused_program_rejects_instructions_that_take_the_config() {
+    let compliance = Keypair::new();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    let mut config = config(&Pubkey::new_unique());
+    config.paused = true;
+    add_config(&mut program_test, &config);
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = set_attester(&compliance.pubkey(), vault_pause_address(&VAULT));
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), TokrizerError::ProgramPaused.into());
+}
+
+#[tokio::test]
+async fn emergency_role_unpauses_a_vault() {
+    let compliance = Keypair::new();
+    let emergency = Keypair::new();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_role(&mut program_test, &emergency.pubkey(), Role::Emergency);
+    add_vault(&mut program_test);
+    add_vault_pause(&mut program_test, &VAULT, true);
+    let mut context = program_test.start_with_context().await;
+
+    send(&mut context, &[set_vault_paused(&emergency.pubkey(), false)], &[&emergency])
+        .await
+        .unwrap();
+
+    let account = context.banks_client.get_account(vault_pause_address(&VAULT)).await.unwrap().unwrap();
+    let vault_pause = VaultPause::deserialize(&mut &account.data[..]).unwrap();
+    assert!(!vault_pause.paused);
+
+    let instruction = set_attester(&compliance.pubkey(), vault_pause_address(&VAULT));
+    send(&mut context, &[instruction], &[&compliance]).await.unwrap();
+}
+
+#[tokio::test]
+async fn vault_pause_needs_the_emergency_role() {
+    let compliance = Keypair::new();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_vault(&mut program_test);
+    add_vault_pause(&mut program_test, &VAULT, true);
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = set_vault_paused(&compliance.pubkey(), false);
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), TokrizerError::MissingRole.into());
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use solana_program::{instruction::AccountMeta, instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTest};
+use solana_sdk::signature::{Keypair, Signer};
+use tokrizer::{
+    error::TokrizerError,
+    instruction::TokrizerInstruction,
+    state::{Allowlist, Key, Role, ALLOWLIST_PREFIX, ALLOWLIST_SIZE},
+};
+
+const VAULT: Pubkey = Pubkey::new_from_array([7; 32]);
+
+fn allowlist_address() -> Pubkey {
+    Pubkey::find_program_address(&[ALLOWLIST_PREFIX.as_bytes(), VAULT.as_ref()], &tokrizer::id()).0
+}
+
+/// A program with an allowlist managed by `compliance_authority`, whose Set Attester needs the Compliance role
+fn program_with_allowlist(compliance_authority: &Pubkey) -> ProgramTest {
+    let mut program_test = program_test();
+    let allowlist = Allowlist {
+        key: Key::AllowlistV1,
+        vault: VAULT,
+        compliance_authority: *compliance_authority,
+        attester: Pubkey::default(),
+        bump: 0,
+    };
+    add_state(&mut program_test, allowlist_address(), &allowlist, ALLOWLIST_SIZE);
+    program_test
+}
+
+fn set_attester(compliance_authority: &Pubkey, attester: &Pubkey, role_account: Pubkey) -> Instruction {
+    tokrizer_instruction(
+        TokrizerInstruction::SetAttester,
+        vec![
+            AccountMeta::new_readonly(*compliance_authority, true),
+            AccountMeta::new(allowlist_address(), false),
+            AccountMeta::new_readonly(*attester, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(role_account, false),
+            AccountMeta::new_readonly(vault_pause_address(&VAULT), false),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn role_holder_is_accepted() {
+    let compliance = Keypair::new();
+    let attester = Pubkey::new_unique();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_role(&mut program_test, &compliance.pubkey(), Role::Compliance);
+    let mut context = program_test.start_with_context().await;
+
+    let role = role_address(&compliance.pubkey(), Role::Compliance);
+    send(&mut context, &[set_attester(&compliance.pubkey(), &attester, role)], &[&compliance])
+        .await
+        .unwrap();
+
+    let account = context.banks_client.get_account(allowlist_address()).await.unwrap().unwrap();
+    let allowlist = Allowlist::deserialize(&mut &account.data[..]).unwrap();
+    assert_eq!(allowlist.attester, attester);
+}
+
+#[tokio::test]
+async fn roles_are_required_before_the_config_exists() {
+    let compliance = Keypair::new();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_role(&mut program_test, &compliance.pubkey(), Role::Compliance);
+    let mut context = program_test.start_with_context().await;
+
+    let role = role_address(&compliance.pubkey(), Role::Compliance);
+    let instruction = set_attester(&compliance.pubkey(), &Pubkey::new_unique(), role);
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), TokrizerError::MissingRole.into());
+}
+
+#[tokio::test]
+async fn wallet_without_the_role_is_rejected() {
+    let compliance = Keypair::new();
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_role(&mut program_test, &compliance.pubkey(), Role::Tokenizer);
+    let mut context = program_test.start_with_context().await;
+
+    let role = role_address(&compliance.pubkey(), Role::Compliance);
+    let instruction = set_attester(&compliance.pubkey(), &Pubkey::new_unique(), role);
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), TokrizerError::MissingRole.into());
+}
+
+#[tokio::test]
+async fn role_account_must_hold_that_wallet_and_role() {
+    let compliance = Keypair::new();
+    let role = role_address(&compliance.pubkey(), Role::Compliance);
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_role_account(&mut program_test, role, &compliance.pubkey(), Role::Emergency);
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = set_attester(&compliance.pubkey(), &Pubkey::new_unique(), role);
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), TokrizerError::MissingRole.into());
+}
+
+#[tokio::test]
+async fn role_account_must_be_owned_by_tokrizer() {
+    let compliance = Keypair::new();
+    let role = role_address(&compliance.pubkey(), Role::Compliance);
+    let mut program_test = program_with_allowlist(&compliance.pubkey());
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    program_test.add_account(
+        role,
+        solana_sdk::account::Account {
+            lamports: 1_000_000_000,
+            data: vec![1; 74],
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = set_attester(&compliance.pubkey(), &Pubkey::new_unique(), role);
+    let result = send(&mut context, &[instruction], &[&compliance]).await;
+    assert_eq!(program_error(result), solana_program::program_error::ProgramError::IllegalOwner);
+}
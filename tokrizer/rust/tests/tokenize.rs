@@ -0,0 +1,168 @@
+// Tokenize creates its accounts through cross-program invocations, which the native processor can't do,
+// so this runs against the BPF builds: `cargo test-bpf --features test-bpf`, with mpl_token_metadata.so and
+// mpl_token_vault.so (e.g. from `solana program dump`) in tests/fixtures.
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use mpl_token_vault::state::{Vault, VaultState};
+use solana_program::{instruction::AccountMeta, program_option::COption, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::{tokio, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account as TokenAccount, Mint};
+use tokrizer::{
+    instruction::{MintArgs, TokenizeArgs, TokrizerInstruction, VaultArgs},
+    state::{Portfolio, Role, DENIED_WALLET_PREFIX, PORTFOLIO_PREFIX, TREASURY_PREFIX},
+};
+
+const SHARES: u64 = 1_000;
+
+fn tokrizer_address(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &tokrizer::id())
+}
+
+/// Add the wrapped SOL mint, which Create Vault opens the Redeem Treasury for
+fn add_native_mint(program_test: &mut ProgramTest) {
+    let mut data = vec![0; Mint::LEN];
+    Mint {
+        mint_authority: COption::None,
+        supply: 0,
+        decimals: 9,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(
+        spl_token::native_mint::id(),
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+#[tokio::test]
+async fn tokenize_mints_deposits_and_fractionalizes() {
+    let tokenizer = Keypair::new();
+    let mut program_test = ProgramTest::new("tokrizer", tokrizer::id(), None);
+    program_test.add_program("mpl_token_metadata", mpl_token_metadata::id(), None);
+    program_test.add_program("mpl_token_vault", mpl_token_vault::id(), None);
+    program_test.add_account(
+        tokenizer.pubkey(),
+        Account {
+            lamports: 10_000_000_000,
+            ..Account::default()
+        },
+    );
+    add_native_mint(&mut program_test);
+    add_config(&mut program_test, &config(&Pubkey::new_unique()));
+    add_role(&mut program_test, &tokenizer.pubkey(), Role::Tokenizer);
+    let mut context = program_test.start_with_context().await;
+
+    let payer = tokenizer.pubkey();
+    let vault_program = mpl_token_vault::id();
+    let metadata_program = mpl_token_metadata::id();
+
+    let mint_seed = "rnft".to_string();
+    let (mint, mint_bump) = tokrizer_address(&[mint_seed.as_bytes(), payer.as_ref(), payer.as_ref()]);
+    let vault_seed = "vault".to_string();
+    let (vault, vault_bump) = tokrizer_address(&[payer.as_ref(), vault_program.as_ref(), vault_seed.as_bytes()]);
+
+    let metadata = Pubkey::find_program_address(
+        &[b"metadata", metadata_program.as_ref(), mint.as_ref()],
+        &metadata_program,
+    )
+    .0;
+    let vault_mint_authority = Pubkey::find_program_address(
+        &[mpl_token_vault::state::PREFIX.as_bytes(), vault_program.as_ref(), vault.as_ref()],
+        &vault_program,
+    )
+    .0;
+    let safety_deposit_box = Pubkey::find_program_address(
+        &[mpl_token_vault::state::PREFIX.as_bytes(), vault.as_ref(), mint.as_ref()],
+        &vault_program,
+    )
+    .0;
+    let fraction_mint = tokrizer_address(&[b"fraction", vault.as_ref(), payer.as_ref()]).0;
+    let fraction_treasury = get_associated_token_address(&vault_mint_authority, &fraction_mint);
+    let token_store = tokrizer_address(&[b"store", vault.as_ref(), mint.as_ref()]).0;
+    let portfolio = tokrizer_address(&[PORTFOLIO_PREFIX.as_bytes(), vault.as_ref()]).0;
+
+    let instruction = tokrizer_instruction(
+        TokrizerInstruction::Tokenize(TokenizeArgs {
+            mint: MintArgs {
+                name: "123 Main St".to_string(),
+                symbol: "TOKR".to_string(),
+                uri: "https://example.com/rnft.json".to_string(),
+                mint_bump,
+                mint_seed,
+            },
+            vault: VaultArgs {
+                vault_bump,
+                vault_seed,
+                allow_further_share_creation: false,
+                fraction_decimals: 0,
+            },
+            number_of_shares: SHARES,
+        }),
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(mint, false),
+            AccountMeta::new(metadata, false),
+            AccountMeta::new(get_associated_token_address(&payer, &mint), false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(vault_mint_authority, false),
+            AccountMeta::new(tokrizer_address(&[b"external", vault.as_ref(), payer.as_ref()]).0, false),
+            AccountMeta::new(fraction_mint, false),
+            AccountMeta::new(
+                get_associated_token_address(&vault_mint_authority, &spl_token::native_mint::id()),
+                false,
+            ),
+            AccountMeta::new(fraction_treasury, false),
+            AccountMeta::new(tokrizer_address(&[b"transfer", vault.as_ref(), mint.as_ref()]).0, false),
+            AccountMeta::new(token_store, false),
+            AccountMeta::new(safety_deposit_box, false),
+            AccountMeta::new(portfolio, false),
+            AccountMeta::new_readonly(vault_pause_address(&vault), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(metadata_program, false),
+            AccountMeta::new_readonly(vault_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+            AccountMeta::new_readonly(tokrizer_address(&[DENIED_WALLET_PREFIX.as_bytes(), payer.as_ref()]).0, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(role_address(&payer, Role::Tokenizer), false),
+            AccountMeta::new(tokrizer_address(&[TREASURY_PREFIX.as_bytes()]).0, false),
+        ],
+    );
+    send(&mut context, &[instruction], &[&tokenizer]).await.unwrap();
+
+    let account = context.banks_client.get_account(vault).await.unwrap().unwrap();
+    let vault_state = Vault::deserialize(&mut &account.data[..]).unwrap();
+    assert!(vault_state.state == VaultState::Active);
+    assert_eq!(vault_state.token_type_count, 1);
+
+    let account = context.banks_client.get_account(fraction_treasury).await.unwrap().unwrap();
+    assert_eq!(TokenAccount::unpack(&account.data).unwrap().amount, SHARES);
+
+    let account = context.banks_client.get_account(token_store).await.unwrap().unwrap();
+    assert_eq!(TokenAccount::unpack(&account.data).unwrap().amount, 1);
+
+    let account = context.banks_client.get_account(portfolio).await.unwrap().unwrap();
+    let portfolio = Portfolio::deserialize(&mut &account.data[..]).unwrap();
+    assert_eq!(portfolio.mints, vec![mint]);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::*;
+use solana_program::{instruction::AccountMeta, instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::tokio;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
+use tokrizer::{
+    error::TokrizerError,
+    instruction::{AmountArgs, TokrizerInstruction},
+    state::{
+        ALLOWLIST_ENTRY_PREFIX, ALLOWLIST_PREFIX, COMPLIANCE_PROFILE_PREFIX, HOLDER_REGISTRY_PREFIX,
+        VAULT_COMPLIANCE_PREFIX,
+    },
+};
+
+fn tokrizer_address(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &tokrizer::id()).0
+}
+
+fn transfer_shares(sender: &Pubkey, destination: &Pubkey, vault: &Pubkey, fraction_mint: &Pubkey) -> Instruction {
+    let allowlist = tokrizer_address(&[ALLOWLIST_PREFIX.as_bytes(), vault.as_ref()]);
+    tokrizer_instruction(
+        TokrizerInstruction::TransferShares(AmountArgs { amount: 1 }),
+        vec![
+            AccountMeta::new(*sender, true),
+            AccountMeta::new(get_associated_token_address(sender, fraction_mint), false),
+            AccountMeta::new_readonly(*destination, false),
+            AccountMeta::new(get_associated_token_address(destination, fraction_mint), false),
+            AccountMeta::new_readonly(*vault, false),
+            AccountMeta::new_readonly(*fraction_mint, false),
+            AccountMeta::new_readonly(allowlist, false),
+            AccountMeta::new_readonly(
+                tokrizer_address(&[ALLOWLIST_ENTRY_PREFIX.as_bytes(), allowlist.as_ref(), sender.as_ref()]),
+                false,
+            ),
+            AccountMeta::new_readonly(
+                tokrizer_address(&[ALLOWLIST_ENTRY_PREFIX.as_bytes(), allowlist.as_ref(), destination.as_ref()]),
+                false,
+            ),
+            AccountMeta::new(tokrizer_address(&[HOLDER_REGISTRY_PREFIX.as_bytes(), fraction_mint.as_ref()]), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(tokrizer_address(&[VAULT_COMPLIANCE_PREFIX.as_bytes(), vault.as_ref()]), false),
+            AccountMeta::new_readonly(tokrizer_address(&[COMPLIANCE_PROFILE_PREFIX.as_bytes(), &[0; 8]]), false),
+            AccountMeta::new_readonly(denied_address(sender), false),
+            AccountMeta::new_readonly(denied_address(destination), false),
+            AccountMeta::new_readonly(vault_pause_address(vault), false),
+            AccountMeta::new_readonly(config_address(), false),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn forged_vault_is_rejected() {
+    let sender = Keypair::new();
+    let destination = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    let fraction_mint = Pubkey::new_unique();
+    let mut program_test = program_test();
+    // A vault of the real fraction mint, but without an allowlist or compliance profile to get in the way
+    add_vault_account(&mut program_test, vault, &active_vault(&fraction_mint, &sender.pubkey()), &Pubkey::new_unique());
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = transfer_shares(&sender.pubkey(), &destination, &vault, &fraction_mint);
+    let result = send(&mut context, &[instruction], &[&sender]).await;
+    assert_eq!(program_error(result), ProgramError::IllegalOwner);
+}
+
+#[tokio::test]
+async fn paused_program_rejects_share_transfers() {
+    let sender = Keypair::new();
+    let vault = Pubkey::new_unique();
+    let fraction_mint = Pubkey::new_unique();
+    let mut program_test = program_test();
+    add_vault_account(&mut program_test, vault, &active_vault(&fraction_mint, &sender.pubkey()), &mpl_token_vault::id());
+    let mut config = config(&Pubkey::new_unique());
+    config.paused = true;
+    add_config(&mut program_test, &config);
+    let mut context = program_test.start_with_context().await;
+
+    let instruction = transfer_shares(&sender.pubkey(), &Pubkey::new_unique(), &vault, &fraction_mint);
+    let result = send(&mut context, &[instruction], &[&sender]).await;
+    assert_eq!(program_error(result), TokrizerError::ProgramPaused.into());
+}
+
+#[tokio::test]
+async fn share_transfers_need_the_config_account() {
+    let sender = Keypair::new();
+    let vault = Pubkey::new_unique();
+    let fraction_mint = Pubkey::new_unique();
+    let mut program_test = program_test();
+    add_vault_account(&mut program_test, vault, &active_vault(&fraction_mint, &sender.pubkey()), &mpl_token_vault::id());
+    let mut context = program_test.start_with_context().await;
+
+    let mut instruction = transfer_shares(&sender.pubkey(), &Pubkey::new_unique(), &vault, &fraction_mint);
+    instruction.accounts.pop();
+    let result = send(&mut context, &[instruction], &[&sender]).await;
+    assert_eq!(program_error(result), ProgramError::NotEnoughAccountKeys);
+}